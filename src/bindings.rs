@@ -0,0 +1,470 @@
+use crate::tputil::{self, Axis, Button};
+
+use piston::input::Key;
+
+pub const BINDINGS_PATH: &str = "bindings.json";
+
+// buttons the game reads, in the order the controls screen lists them
+pub const GAME_BUTTONS: [Button; 8] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::LeftTrigger,
+    Button::RightTrigger,
+    Button::Select,
+    Button::Start,
+];
+
+pub fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::South => "South",
+        Button::East => "East",
+        Button::North => "North",
+        Button::West => "West",
+        Button::C => "C",
+        Button::Z => "Z",
+        Button::LeftTrigger => "LeftTrigger",
+        Button::LeftTrigger2 => "LeftTrigger2",
+        Button::RightTrigger => "RightTrigger",
+        Button::RightTrigger2 => "RightTrigger2",
+        Button::Select => "Select",
+        Button::Start => "Start",
+        Button::Mode => "Mode",
+        Button::LeftThumb => "LeftThumb",
+        Button::RightThumb => "RightThumb",
+        Button::DPadUp => "DPadUp",
+        Button::DPadDown => "DPadDown",
+        Button::DPadLeft => "DPadLeft",
+        Button::DPadRight => "DPadRight",
+        _ => "Unknown",
+    }
+}
+
+pub fn axis_name(axis: gilrs::Axis) -> &'static str {
+    match axis {
+        gilrs::Axis::LeftStickX => "LeftStickX",
+        gilrs::Axis::LeftStickY => "LeftStickY",
+        gilrs::Axis::LeftZ => "LeftZ",
+        gilrs::Axis::RightStickX => "RightStickX",
+        gilrs::Axis::RightStickY => "RightStickY",
+        gilrs::Axis::RightZ => "RightZ",
+        gilrs::Axis::DPadX => "DPadX",
+        gilrs::Axis::DPadY => "DPadY",
+        _ => "Unknown",
+    }
+}
+
+fn button_by_name(name: &str) -> Option<Button> {
+    tputil::GAMEPAD_BUTTONS
+        .iter()
+        .cloned()
+        .find(|&button| button_name(button) == name)
+}
+
+fn axis_by_name(name: &str) -> Option<gilrs::Axis> {
+    tputil::GAMEPAD_AXES
+        .iter()
+        .cloned()
+        .find(|&axis| axis_name(axis) == name)
+}
+
+// gilrs types aren't serializable, so the file refers to them by name
+mod button_keys {
+    use super::{button_by_name, button_name, Button, Key};
+    use serde::{de::Error, Deserialize};
+
+    pub fn serialize<S: serde::Serializer>(
+        buttons: &[(Button, Key)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            buttons
+                .iter()
+                .map(|(button, key)| (button_name(*button), key)),
+        )
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(Button, Key)>, D::Error> {
+        std::collections::BTreeMap::<String, Key>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, key)| match button_by_name(&name) {
+                Some(button) => Ok((button, key)),
+                None => Err(D::Error::custom(format!("unknown button {}", name))),
+            })
+            .collect()
+    }
+}
+
+mod button_buttons {
+    use super::{button_by_name, button_name, Button};
+    use serde::{de::Error, Deserialize};
+
+    pub fn serialize<S: serde::Serializer>(
+        buttons: &[(Button, Button)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            buttons
+                .iter()
+                .map(|(button, physical)| (button_name(*button), button_name(*physical))),
+        )
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(Button, Button)>, D::Error> {
+        std::collections::BTreeMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(
+                |(name, physical)| match (button_by_name(&name), button_by_name(&physical)) {
+                    (Some(button), Some(physical)) => Ok((button, physical)),
+                    (None, _) => Err(D::Error::custom(format!("unknown button {}", name))),
+                    (_, None) => Err(D::Error::custom(format!("unknown button {}", physical))),
+                },
+            )
+            .collect()
+    }
+}
+
+mod axes {
+    use super::{axis_by_name, axis_name};
+    use serde::{de::Error, Deserialize};
+
+    pub fn serialize<S: serde::Serializer>(
+        axes: &[gilrs::Axis],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(axes.iter().map(|&axis| axis_name(axis)))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<gilrs::Axis>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|name| {
+                axis_by_name(&name)
+                    .ok_or_else(|| D::Error::custom(format!("unknown axis {}", name)))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct KeyLayout {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+    #[serde(with = "button_keys")]
+    pub buttons: Vec<(Button, Key)>,
+}
+
+impl KeyLayout {
+    pub fn defaults() -> Vec<KeyLayout> {
+        vec![
+            KeyLayout {
+                up: Key::Up,
+                down: Key::Down,
+                left: Key::Left,
+                right: Key::Right,
+                buttons: vec![
+                    (Button::South, Key::RShift),
+                    (Button::East, Key::RCtrl),
+                    (Button::North, Key::Slash),
+                    (Button::West, Key::Period),
+                    (Button::LeftTrigger, Key::Comma),
+                    (Button::RightTrigger, Key::Quote),
+                    (Button::Select, Key::Backspace),
+                    (Button::Start, Key::Return),
+                ],
+            },
+            KeyLayout {
+                up: Key::W,
+                down: Key::S,
+                left: Key::A,
+                right: Key::D,
+                buttons: vec![
+                    (Button::South, Key::Space),
+                    (Button::East, Key::LShift),
+                    (Button::North, Key::E),
+                    (Button::West, Key::Q),
+                    (Button::LeftTrigger, Key::Z),
+                    (Button::RightTrigger, Key::X),
+                    (Button::Select, Key::Backquote),
+                    (Button::Start, Key::Tab),
+                ],
+            },
+            KeyLayout {
+                up: Key::I,
+                down: Key::K,
+                left: Key::J,
+                right: Key::L,
+                buttons: vec![
+                    (Button::South, Key::H),
+                    (Button::East, Key::N),
+                    (Button::North, Key::U),
+                    (Button::West, Key::Y),
+                    (Button::LeftTrigger, Key::B),
+                    (Button::RightTrigger, Key::M),
+                    (Button::Select, Key::G),
+                    (Button::Start, Key::P),
+                ],
+            },
+        ]
+    }
+
+    pub fn key(&self, button: Button) -> Option<Key> {
+        let direction = match button {
+            Button::DPadUp => Some(self.up),
+            Button::DPadDown => Some(self.down),
+            Button::DPadLeft => Some(self.left),
+            Button::DPadRight => Some(self.right),
+            _ => None,
+        };
+        direction.or_else(|| {
+            self.buttons
+                .iter()
+                .find(|&&(b, _)| b == button)
+                .map(|&(_, key)| key)
+        })
+    }
+
+    pub fn set_key(&mut self, button: Button, key: Key) {
+        match button {
+            Button::DPadUp => self.up = key,
+            Button::DPadDown => self.down = key,
+            Button::DPadLeft => self.left = key,
+            Button::DPadRight => self.right = key,
+            _ => {
+                self.buttons.retain(|&(b, _)| b != button);
+                self.buttons.push((button, key));
+            }
+        }
+    }
+
+    fn buttons_on(&self, key: Key) -> Vec<Button> {
+        let directions = [
+            (Button::DPadUp, self.up),
+            (Button::DPadDown, self.down),
+            (Button::DPadLeft, self.left),
+            (Button::DPadRight, self.right),
+        ];
+        directions
+            .iter()
+            .chain(self.buttons.iter())
+            .filter(|&&(_, k)| k == key)
+            .map(|&(button, _)| button)
+            .collect()
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+    // game buttons read from another physical button, the rest are read as is
+    #[serde(with = "button_buttons")]
+    pub buttons: Vec<(Button, Button)>,
+    #[serde(with = "axes")]
+    pub x_axes: Vec<gilrs::Axis>,
+    #[serde(with = "axes")]
+    pub y_axes: Vec<gilrs::Axis>,
+    pub deadzone: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        GamepadBindings {
+            buttons: Vec::new(),
+            x_axes: vec![gilrs::Axis::LeftStickX, gilrs::Axis::DPadX],
+            y_axes: vec![gilrs::Axis::LeftStickY, gilrs::Axis::DPadY],
+            deadzone: 0.1,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+impl GamepadBindings {
+    pub fn button(&self, button: Button) -> Button {
+        self.buttons
+            .iter()
+            .find(|&&(b, _)| b == button)
+            .map(|&(_, physical)| physical)
+            .unwrap_or(button)
+    }
+
+    pub fn set_button(&mut self, button: Button, physical: Button) {
+        self.buttons.retain(|&(b, _)| b != button);
+        if button != physical {
+            self.buttons.push((button, physical));
+        }
+    }
+
+    pub fn axis<F: Fn(gilrs::Axis) -> f32>(&self, value: F, axis: Axis) -> f32 {
+        let (axes, invert) = match axis {
+            Axis::X => (&self.x_axes, self.invert_x),
+            Axis::Y => (&self.y_axes, self.invert_y),
+        };
+        let value = axes
+            .iter()
+            .map(|&axis| value(axis))
+            .sum::<f32>()
+            .clamp(-1.0, 1.0);
+        if value.abs() < self.deadzone {
+            0.0
+        } else if invert {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub keyboards: Vec<KeyLayout>,
+    // for gamepads without an entry of their own
+    pub gamepad: GamepadBindings,
+    // by the name the controller reports
+    pub gamepads: std::collections::BTreeMap<String, GamepadBindings>,
+    // where changes are written, None for sessions that shouldn't persist them
+    #[serde(skip)]
+    path: Option<std::path::PathBuf>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keyboards: KeyLayout::defaults(),
+            gamepad: GamepadBindings::default(),
+            gamepads: std::collections::BTreeMap::new(),
+            path: None,
+        }
+    }
+}
+
+impl Bindings {
+    pub fn load() -> Bindings {
+        let mut bindings = match std::fs::read_to_string(BINDINGS_PATH) {
+            Ok(src) => serde_json::from_str(&src).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid {}: {}", BINDINGS_PATH, err);
+                Bindings::default()
+            }),
+            Err(_) => Bindings::default(),
+        };
+        if bindings.keyboards.is_empty() {
+            bindings.keyboards = KeyLayout::defaults();
+        }
+        bindings.path = Some(BINDINGS_PATH.into());
+        bindings
+    }
+
+    pub fn save(&self) {
+        if let Some(ref path) = self.path {
+            let result = serde_json::to_string_pretty(self)
+                .map_err(std::io::Error::from)
+                .and_then(|src| std::fs::write(path, src));
+            if let Err(err) = result {
+                eprintln!("Failed to save {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    // whatever used key before, on any keyboard layout, gets the key button had,
+    // so that no key does two things; returns false if there is nothing to swap
+    pub fn set_key(&mut self, slot: usize, button: Button, key: Key) -> bool {
+        let old = match self.keyboards.get(slot) {
+            Some(layout) => layout.key(button),
+            None => return false,
+        };
+        let conflicts: Vec<(usize, Button)> = self
+            .keyboards
+            .iter()
+            .enumerate()
+            .flat_map(|(i, layout)| layout.buttons_on(key).into_iter().map(move |b| (i, b)))
+            .filter(|&conflict| conflict != (slot, button))
+            .collect();
+        match old {
+            Some(old) => {
+                for (i, other) in conflicts {
+                    self.keyboards[i].set_key(other, old);
+                }
+            }
+            None if !conflicts.is_empty() => return false,
+            None => {}
+        }
+        self.keyboards[slot].set_key(button, key);
+        true
+    }
+
+    pub fn gamepad(&self, name: &str) -> &GamepadBindings {
+        self.gamepads.get(name).unwrap_or(&self.gamepad)
+    }
+
+    // starts from the shared bindings the first time a controller is changed
+    pub fn gamepad_mut(&mut self, name: &str) -> &mut GamepadBindings {
+        let fallback = &self.gamepad;
+        self.gamepads
+            .entry(name.to_owned())
+            .or_insert_with(|| fallback.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keys_are_unique() {
+        let bindings = Bindings::default();
+        for (i, layout) in bindings.keyboards.iter().enumerate() {
+            for &(_, key) in &layout.buttons {
+                let users: usize = bindings
+                    .keyboards
+                    .iter()
+                    .map(|other| other.buttons_on(key).len())
+                    .sum();
+                assert_eq!(users, 1, "{:?} on keyboard {} is used twice", key, i + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn set_key_swaps_within_a_layout() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.set_key(0, Button::South, Key::RCtrl));
+        assert_eq!(bindings.keyboards[0].key(Button::South), Some(Key::RCtrl));
+        assert_eq!(bindings.keyboards[0].key(Button::East), Some(Key::RShift));
+
+        assert!(bindings.set_key(0, Button::DPadUp, Key::Down));
+        assert_eq!(bindings.keyboards[0].up, Key::Down);
+        assert_eq!(bindings.keyboards[0].down, Key::Up);
+    }
+
+    #[test]
+    fn set_key_swaps_across_layouts() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.set_key(0, Button::Start, Key::Space));
+        assert_eq!(bindings.keyboards[0].key(Button::Start), Some(Key::Space));
+        assert_eq!(bindings.keyboards[1].key(Button::South), Some(Key::Return));
+    }
+
+    #[test]
+    fn set_key_refuses_without_a_key_to_swap() {
+        let mut bindings = Bindings::default();
+        bindings.keyboards[0]
+            .buttons
+            .retain(|&(button, _)| button != Button::North);
+        assert!(!bindings.set_key(0, Button::North, Key::W));
+        assert_eq!(bindings.keyboards[0].key(Button::North), None);
+        assert!(bindings.set_key(0, Button::North, Key::F1));
+        assert_eq!(bindings.keyboards[0].key(Button::North), Some(Key::F1));
+    }
+}
@@ -2,10 +2,17 @@ use crate::tputil;
 
 pub type SpaceID = usize;
 
-#[derive(Copy, Clone)]
+pub const BOARDS_DIR: &str = "assets/boards";
+pub const START_SPACE: SpaceID = 0;
+
+#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SpaceType {
     Positive,
     Negative,
+    Shop,
+    Event,
+    Duel,
 }
 
 #[derive(Clone)]
@@ -23,195 +30,182 @@ pub struct SpaceTransition {
 
 #[derive(Clone)]
 pub struct Board {
+    pub name: String,
+    pub description: String,
     pub spaces: Vec<Space>,
+    // the file the board was loaded from, None for the built-in board
+    pub source: Option<std::path::PathBuf>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBoard {
+    name: String,
+    #[serde(default)]
+    description: String,
+    spaces: Vec<serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSpace {
+    id: SpaceID,
+    pos: [f64; 2],
+    space_type: SpaceType,
+    transitions: Vec<SpaceID>,
+}
+
+impl From<RawSpace> for Space {
+    fn from(raw: RawSpace) -> Self {
+        Space {
+            id: raw.id,
+            pos: tputil::Point2D::new(raw.pos[0], raw.pos[1]),
+            space_type: raw.space_type,
+            transitions: raw
+                .transitions
+                .into_iter()
+                .map(|to| SpaceTransition { to })
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BoardLoadError {
+    Io(std::io::Error),
+    Syntax(serde_json::Error),
+    Space {
+        index: usize,
+        id: Option<SpaceID>,
+        error: serde_json::Error,
+    },
+}
+
+impl std::fmt::Display for BoardLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardLoadError::Io(err) => write!(f, "failed to read board: {}", err),
+            BoardLoadError::Syntax(err) => write!(f, "invalid board: {}", err),
+            BoardLoadError::Space { index, id, error } => match id {
+                Some(id) => write!(f, "invalid space #{} (id {}): {}", index, id, error),
+                None => write!(f, "invalid space #{}: {}", index, error),
+            },
+        }
+    }
+}
+
+impl std::error::Error for BoardLoadError {}
+
+impl From<std::io::Error> for BoardLoadError {
+    fn from(err: std::io::Error) -> Self {
+        BoardLoadError::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoardProblem {
+    Empty,
+    MissingStart,
+    DuplicateId(SpaceID),
+    DanglingTransition { from: SpaceID, to: SpaceID },
+    DeadEnd(SpaceID),
+    Unreachable(SpaceID),
+    IslandCycle(Box<[SpaceID]>),
+}
+
+impl BoardProblem {
+    pub fn severity(&self) -> Severity {
+        match self {
+            BoardProblem::Unreachable(_) | BoardProblem::IslandCycle(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for BoardProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardProblem::Empty => write!(f, "board has no spaces"),
+            BoardProblem::MissingStart => {
+                write!(f, "there is no start space (id {})", START_SPACE)
+            }
+            BoardProblem::DuplicateId(id) => write!(f, "more than one space has id {}", id),
+            BoardProblem::DanglingTransition { from, to } => write!(
+                f,
+                "space {} has a transition to space {}, which does not exist",
+                from, to
+            ),
+            BoardProblem::DeadEnd(id) => write!(f, "space {} has no transitions", id),
+            BoardProblem::Unreachable(id) => {
+                write!(f, "space {} can't be reached from the start", id)
+            }
+            BoardProblem::IslandCycle(ids) => {
+                write!(f, "players who reach spaces {:?} can never leave them", ids)
+            }
+        }
+    }
 }
 
 impl Board {
     pub fn get_default_board() -> Board {
-        Board {
-            spaces: vec![
-                Space {
-                    id: 0,
-                    pos: tputil::Point2D::new(20.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 1 }]),
-                },
-                Space {
-                    id: 1,
-                    pos: tputil::Point2D::new(16.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 2 }]),
-                },
-                Space {
-                    id: 2,
-                    pos: tputil::Point2D::new(12.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 3 }, SpaceTransition { to: 13 }]),
-                },
-                Space {
-                    id: 3,
-                    pos: tputil::Point2D::new(9.0, 20.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 4 }]),
-                },
-                Space {
-                    id: 4,
-                    pos: tputil::Point2D::new(5.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 5 }]),
-                },
-                Space {
-                    id: 5,
-                    pos: tputil::Point2D::new(1.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 6 }]),
-                },
-                Space {
-                    id: 6,
-                    pos: tputil::Point2D::new(-3.0, 20.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 7 }]),
-                },
-                Space {
-                    id: 7,
-                    pos: tputil::Point2D::new(-3.0, 16.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 8 }]),
-                },
-                Space {
-                    id: 8,
-                    pos: tputil::Point2D::new(-3.0, 12.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 9 }]),
-                },
-                Space {
-                    id: 9,
-                    pos: tputil::Point2D::new(-3.0, 8.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 10 }]),
-                },
-                Space {
-                    id: 10,
-                    pos: tputil::Point2D::new(-3.0, 4.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 11 }]),
-                },
-                Space {
-                    id: 11,
-                    pos: tputil::Point2D::new(-3.0, 0.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 12 }]),
-                },
-                Space {
-                    id: 12,
-                    pos: tputil::Point2D::new(0.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 18 }]),
-                },
-                Space {
-                    id: 13,
-                    pos: tputil::Point2D::new(9.0, 17.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 14 }]),
-                },
-                Space {
-                    id: 14,
-                    pos: tputil::Point2D::new(6.0, 14.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 15 }]),
-                },
-                Space {
-                    id: 15,
-                    pos: tputil::Point2D::new(3.0, 11.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 16 }]),
-                },
-                Space {
-                    id: 16,
-                    pos: tputil::Point2D::new(3.0, 7.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 17 }]),
-                },
-                Space {
-                    id: 17,
-                    pos: tputil::Point2D::new(3.0, 3.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 18 }]),
-                },
-                Space {
-                    id: 18,
-                    pos: tputil::Point2D::new(3.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 19 }]),
-                },
-                Space {
-                    id: 19,
-                    pos: tputil::Point2D::new(7.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 20 }]),
-                },
-                Space {
-                    id: 20,
-                    pos: tputil::Point2D::new(11.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 21 }, SpaceTransition { to: 26 }]),
-                },
-                Space {
-                    id: 21,
-                    pos: tputil::Point2D::new(15.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 22 }]),
-                },
-                Space {
-                    id: 22,
-                    pos: tputil::Point2D::new(19.0, 0.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 23 }]),
-                },
-                Space {
-                    id: 23,
-                    pos: tputil::Point2D::new(22.0, 0.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 24 }]),
-                },
-                Space {
-                    id: 24,
-                    pos: tputil::Point2D::new(22.0, 4.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 25 }]),
-                },
-                Space {
-                    id: 25,
-                    pos: tputil::Point2D::new(22.0, 8.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 28 }]),
-                },
-                Space {
-                    id: 26,
-                    pos: tputil::Point2D::new(15.0, 4.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 27 }]),
-                },
-                Space {
-                    id: 27,
-                    pos: tputil::Point2D::new(18.0, 7.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 25 }]),
-                },
-                Space {
-                    id: 28,
-                    pos: tputil::Point2D::new(22.0, 11.0),
-                    space_type: SpaceType::Negative,
-                    transitions: Box::new([SpaceTransition { to: 29 }]),
-                },
-                Space {
-                    id: 29,
-                    pos: tputil::Point2D::new(22.0, 15.0),
-                    space_type: SpaceType::Positive,
-                    transitions: Box::new([SpaceTransition { to: 0 }]),
-                },
-            ],
-        }
+        Board::from_json(include_str!("../assets/boards/default.json"))
+            .expect("Failed to parse built-in board")
+    }
+
+    pub fn from_json(src: &str) -> Result<Board, BoardLoadError> {
+        let raw: RawBoard = serde_json::from_str(src).map_err(BoardLoadError::Syntax)?;
+        let spaces = raw
+            .spaces
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let id = value
+                    .get("id")
+                    .and_then(serde_json::Value::as_u64)
+                    .map(|id| id as SpaceID);
+                serde_json::from_value::<RawSpace>(value)
+                    .map(Space::from)
+                    .map_err(|error| BoardLoadError::Space { index, id, error })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Board {
+            name: raw.name,
+            description: raw.description,
+            spaces,
+            source: None,
+        })
+    }
+
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Board, BoardLoadError> {
+        let mut board = Board::from_json(&std::fs::read_to_string(&path)?)?;
+        board.source = Some(path.as_ref().to_owned());
+        Ok(board)
+    }
+
+    pub fn load_dir<P: AsRef<std::path::Path>>(
+        dir: P,
+    ) -> std::io::Result<Vec<(std::path::PathBuf, Result<Board, BoardLoadError>)>> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension() == Some(std::ffi::OsStr::new("json")));
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let board = Board::load(&path);
+                (path, board)
+            })
+            .collect())
     }
 
     pub fn get_space(&self, id: SpaceID) -> Option<&Space> {
@@ -222,4 +216,335 @@ impl Board {
         }
         None
     }
+
+    pub fn bounds(&self) -> (tputil::Point2D, tputil::Point2D) {
+        let mut min = tputil::Point2D::new(f64::INFINITY, f64::INFINITY);
+        let mut max = tputil::Point2D::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for space in &self.spaces {
+            min.x = min.x.min(space.pos.x);
+            min.y = min.y.min(space.pos.y);
+            max.x = max.x.max(space.pos.x);
+            max.y = max.y.max(space.pos.y);
+        }
+        if self.spaces.is_empty() {
+            (tputil::Point2D::ZERO, tputil::Point2D::ZERO)
+        } else {
+            (min, max)
+        }
+    }
+
+    pub fn validate(&self) -> Vec<BoardProblem> {
+        let mut problems = Vec::new();
+        if self.spaces.is_empty() {
+            problems.push(BoardProblem::Empty);
+            return problems;
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for space in &self.spaces {
+            if !seen.insert(space.id) {
+                problems.push(BoardProblem::DuplicateId(space.id));
+            }
+            if space.transitions.is_empty() {
+                problems.push(BoardProblem::DeadEnd(space.id));
+            }
+            for transition in space.transitions.iter() {
+                if self.get_space(transition.to).is_none() {
+                    problems.push(BoardProblem::DanglingTransition {
+                        from: space.id,
+                        to: transition.to,
+                    });
+                }
+            }
+        }
+
+        if self.get_space(START_SPACE).is_none() {
+            problems.push(BoardProblem::MissingStart);
+            return problems;
+        }
+
+        let reachable = self.reachable_from(START_SPACE);
+        for space in &self.spaces {
+            if !reachable.contains(&space.id) {
+                problems.push(BoardProblem::Unreachable(space.id));
+            }
+        }
+
+        // dead ends and duplicate ids already trap players, and confuse the search
+        if problems
+            .iter()
+            .any(|problem| problem.severity() == Severity::Error)
+        {
+            return problems;
+        }
+        // a cycle is only fine if it is the whole board, otherwise the star
+        // can end up somewhere players stuck in it never get back to
+        problems.extend(
+            self.closed_cycles()
+                .into_iter()
+                .filter(|cycle| reachable.contains(&cycle[0]) && cycle.len() < reachable.len())
+                .map(BoardProblem::IslandCycle),
+        );
+
+        problems
+    }
+
+    // number of moves along transitions, None if `to` can't be reached
+    pub fn distance(&self, from: SpaceID, to: SpaceID) -> Option<usize> {
+        let mut visited = std::collections::HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back((from, 0));
+        while let Some((id, distance)) = queue.pop_front() {
+            if id == to {
+                return Some(distance);
+            }
+            if let Some(space) = self.get_space(id) {
+                if visited.insert(id) {
+                    for transition in space.transitions.iter() {
+                        queue.push_back((transition.to, distance + 1));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn reachable_from(&self, start: SpaceID) -> std::collections::HashSet<SpaceID> {
+        let mut reachable = std::collections::HashSet::new();
+        let mut queue = vec![start];
+        while let Some(id) = queue.pop() {
+            if let Some(space) = self.get_space(id) {
+                if reachable.insert(id) {
+                    queue.extend(space.transitions.iter().map(|transition| transition.to));
+                }
+            }
+        }
+        reachable
+    }
+
+    // groups of spaces that all lead to each other, but never anywhere else
+    fn closed_cycles(&self) -> Vec<Box<[SpaceID]>> {
+        let reachable: std::collections::HashMap<_, _> = self
+            .spaces
+            .iter()
+            .map(|space| (space.id, self.reachable_from(space.id)))
+            .collect();
+
+        let mut cycles = Vec::new();
+        let mut handled = std::collections::HashSet::new();
+        for space in &self.spaces {
+            if handled.contains(&space.id) {
+                continue;
+            }
+            let from_space = &reachable[&space.id];
+            let loops_back =
+                space
+                    .transitions
+                    .iter()
+                    .any(|transition| match reachable.get(&transition.to) {
+                        Some(from_next) => from_next.contains(&space.id),
+                        None => false,
+                    });
+            let closed = from_space
+                .iter()
+                .all(|other| reachable[other].contains(&space.id));
+            if loops_back && closed {
+                let mut cycle: Vec<_> = from_space.iter().cloned().collect();
+                cycle.sort();
+                handled.extend(cycle.iter().cloned());
+                cycles.push(cycle.into_boxed_slice());
+            }
+        }
+        cycles
+    }
+}
+
+// saved games refer to their board by source instead of storing a copy
+pub mod source {
+    use super::Board;
+    use serde::{de::Error, Deserialize, Serialize};
+
+    pub fn serialize<S: serde::Serializer>(
+        board: &Board,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        board.source.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Board, D::Error> {
+        match Option::<std::path::PathBuf>::deserialize(deserializer)? {
+            Some(path) => Board::load(&path)
+                .map_err(|err| D::Error::custom(format!("{}: {}", path.display(), err))),
+            None => Ok(Board::get_default_board()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_board_parses() {
+        let board = Board::get_default_board();
+        assert_eq!(board.name, "Crossroads");
+        assert_eq!(board.spaces.len(), 30);
+        assert!(board.source.is_none());
+    }
+
+    #[test]
+    fn from_json_reports_syntax_errors() {
+        match Board::from_json("{ \"name\": ") {
+            Err(BoardLoadError::Syntax(_)) => {}
+            other => panic!("expected a syntax error, got {:?}", other.err()),
+        }
+        match Board::from_json(r#"{ "name": "x", "spaces": [], "colour": 1 }"#) {
+            Err(BoardLoadError::Syntax(_)) => {}
+            other => panic!("expected a syntax error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn from_json_points_at_the_broken_space() {
+        let src = r#"{
+            "name": "x",
+            "spaces": [
+                { "id": 0, "pos": [0, 0], "space_type": "positive", "transitions": [5] },
+                { "id": 5, "pos": [1, 0], "space_type": "purple", "transitions": [0] }
+            ]
+        }"#;
+        match Board::from_json(src) {
+            Err(BoardLoadError::Space { index, id, .. }) => {
+                assert_eq!(index, 1);
+                assert_eq!(id, Some(5));
+            }
+            other => panic!("expected a space error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn from_json_reports_spaces_without_ids() {
+        let src = r#"{
+            "name": "x",
+            "spaces": [{ "pos": [0, 0], "space_type": "positive", "transitions": [] }]
+        }"#;
+        match Board::from_json(src) {
+            Err(BoardLoadError::Space { index, id, .. }) => {
+                assert_eq!(index, 0);
+                assert_eq!(id, None);
+            }
+            other => panic!("expected a space error, got {:?}", other.err()),
+        }
+    }
+
+    fn board(spaces: &[(SpaceID, &[SpaceID])]) -> Board {
+        Board {
+            name: "test".to_owned(),
+            description: String::new(),
+            spaces: spaces
+                .iter()
+                .enumerate()
+                .map(|(index, &(id, transitions))| Space {
+                    id,
+                    pos: tputil::Point2D::new(index as f64, 0.0),
+                    space_type: SpaceType::Positive,
+                    transitions: transitions
+                        .iter()
+                        .map(|&to| SpaceTransition { to })
+                        .collect::<Vec<_>>()
+                        .into_boxed_slice(),
+                })
+                .collect(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn default_board_is_valid() {
+        assert_eq!(Board::get_default_board().validate(), vec![]);
+    }
+
+    #[test]
+    fn validate_rejects_empty_boards() {
+        assert_eq!(board(&[]).validate(), vec![BoardProblem::Empty]);
+    }
+
+    #[test]
+    fn validate_finds_broken_spaces() {
+        let problems = board(&[(0, &[1, 7]), (1, &[]), (1, &[0])]).validate();
+        assert!(problems.contains(&BoardProblem::DanglingTransition { from: 0, to: 7 }));
+        assert!(problems.contains(&BoardProblem::DeadEnd(1)));
+        assert!(problems.contains(&BoardProblem::DuplicateId(1)));
+        assert!(problems.iter().all(|p| p.severity() == Severity::Error));
+    }
+
+    #[test]
+    fn validate_needs_a_start_space() {
+        assert_eq!(
+            board(&[(1, &[2]), (2, &[1])]).validate(),
+            vec![BoardProblem::MissingStart]
+        );
+    }
+
+    #[test]
+    fn validate_warns_about_unreachable_spaces() {
+        let problems = board(&[(0, &[1]), (1, &[0]), (2, &[0])]).validate();
+        assert_eq!(problems, vec![BoardProblem::Unreachable(2)]);
+        assert_eq!(problems[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn validate_warns_about_island_cycles() {
+        // from 1 players either loop on 2 forever or between 3 and 4 forever
+        let problems =
+            board(&[(0, &[1]), (1, &[2, 3]), (2, &[2]), (3, &[4]), (4, &[3])]).validate();
+        assert_eq!(
+            problems,
+            vec![
+                BoardProblem::IslandCycle(vec![2].into_boxed_slice()),
+                BoardProblem::IslandCycle(vec![3, 4].into_boxed_slice()),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_warns_about_a_cycle_after_a_lead_in() {
+        // nobody comes back to 0 once they left it
+        let problems = board(&[(0, &[1]), (1, &[2]), (2, &[1])]).validate();
+        assert_eq!(
+            problems,
+            vec![BoardProblem::IslandCycle(vec![1, 2].into_boxed_slice())]
+        );
+    }
+
+    #[test]
+    fn validate_accepts_a_single_loop() {
+        assert_eq!(
+            board(&[(0, &[1]), (1, &[2, 0]), (2, &[0])]).validate(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn distance_follows_transitions() {
+        let map = board(&[(0, &[1, 3]), (1, &[2]), (2, &[0]), (3, &[2])]);
+        assert_eq!(map.distance(0, 0), Some(0));
+        assert_eq!(map.distance(0, 1), Some(1));
+        // through 1 or 3, whichever comes first
+        assert_eq!(map.distance(0, 2), Some(2));
+        // transitions only go one way
+        assert_eq!(map.distance(1, 0), Some(2));
+        assert_eq!(map.distance(3, 1), Some(3));
+    }
+
+    #[test]
+    fn distance_to_unreachable_spaces_is_none() {
+        let map = board(&[(0, &[1]), (1, &[0]), (2, &[0]), (3, &[3])]);
+        assert_eq!(map.distance(0, 2), None);
+        assert_eq!(map.distance(3, 0), None);
+        assert_eq!(map.distance(0, 42), None);
+        assert_eq!(map.distance(2, 1), Some(2));
+    }
 }
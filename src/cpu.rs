@@ -0,0 +1,256 @@
+use crate::board;
+use crate::game;
+use crate::items;
+use crate::states::ingame::{DieRollState, GameInfo};
+
+use rand::Rng;
+
+// how long CPU players wait before acting, so humans can follow along
+pub const THINK_TIME: f64 = 0.8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    // chance of picking at random instead of making the best choice
+    fn mistake_chance(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 0.2,
+            Difficulty::Hard => 0.0,
+        }
+    }
+
+    pub fn blunders(self, rng: &mut game::GameRng) -> bool {
+        rng.gen::<f64>() < self.mistake_chance()
+    }
+
+    // longest extra wait on top of THINK_TIME
+    fn hesitation(self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.0,
+            Difficulty::Normal => 0.6,
+            Difficulty::Hard => 0.3,
+        }
+    }
+}
+
+// varies, so a CPU player's die doesn't stop on the same number every turn
+pub fn think_time(difficulty: Difficulty, rng: &mut game::GameRng) -> f64 {
+    THINK_TIME + rng.gen::<f64>() * difficulty.hesitation()
+}
+
+fn star_distance(game: &GameInfo, from: board::SpaceID) -> usize {
+    game.map
+        .distance(from, game.star_space)
+        .unwrap_or(usize::MAX)
+}
+
+pub fn choose_transition(
+    game: &GameInfo,
+    player: usize,
+    difficulty: Difficulty,
+    rng: &mut game::GameRng,
+) -> usize {
+    let space = match game.map.get_space(game.players[player].space) {
+        Some(space) => space,
+        None => return 0,
+    };
+    if difficulty.blunders(rng) {
+        return rng.gen_range(0, space.transitions.len());
+    }
+    (0..space.transitions.len())
+        .min_by_key(|&i| star_distance(game, space.transitions[i].to))
+        .unwrap_or(0)
+}
+
+// index of the item to use before rolling, if any
+pub fn choose_item(
+    game: &GameInfo,
+    player: usize,
+    difficulty: Difficulty,
+    rng: &mut game::GameRng,
+) -> Option<usize> {
+    let info = &game.players[player];
+    if difficulty.blunders(rng) {
+        return None;
+    }
+    let distance = star_distance(game, info.space);
+    let richest_other = (0..game.players.len())
+        .filter(|&i| i != player)
+        .map(|i| game.players[i].coins)
+        .max()
+        .unwrap_or(0);
+    let wanted: &[items::Item] = if info.coins >= GameInfo::STAR_PRICE {
+        &[
+            items::Item::WarpToStar,
+            items::Item::SuperDice,
+            items::Item::DoubleDice,
+        ]
+    } else if richest_other >= items::Item::STEAL_AMOUNT {
+        &[items::Item::StealCoins]
+    } else {
+        &[]
+    };
+    wanted
+        .iter()
+        // dice only help when the star is out of reach of a normal roll
+        .filter(|&&item| {
+            item == items::Item::WarpToStar || distance > usize::from(DieRollState::MAX_NUMBER)
+        })
+        .find_map(|item| info.items.iter().position(|other| other == item))
+}
+
+pub fn choose_purchase(
+    game: &GameInfo,
+    player: usize,
+    difficulty: Difficulty,
+    rng: &mut game::GameRng,
+) -> Option<items::Item> {
+    let info = &game.players[player];
+    if info.items.len() >= items::MAX_ITEMS {
+        return None;
+    }
+    let affordable: Vec<items::Item> = items::Item::ALL
+        .iter()
+        .cloned()
+        .filter(|item| item.price() <= info.coins)
+        .collect();
+    if affordable.is_empty() {
+        return None;
+    }
+    if difficulty.blunders(rng) {
+        return Some(affordable[rng.gen_range(0, affordable.len())]);
+    }
+    // keep enough coins for a star unless the item gets there
+    affordable
+        .into_iter()
+        .filter(|&item| {
+            item == items::Item::WarpToStar || info.coins - item.price() >= GameInfo::STAR_PRICE
+        })
+        .max_by_key(|item| item.price())
+}
+
+// opponent index into `opponents` and the wager
+pub fn choose_duel(
+    game: &GameInfo,
+    player: usize,
+    opponents: &[usize],
+    difficulty: Difficulty,
+    rng: &mut game::GameRng,
+) -> (usize, u16) {
+    let opponent = if difficulty.blunders(rng) {
+        rng.gen_range(0, opponents.len())
+    } else {
+        (0..opponents.len())
+            .max_by_key(|&i| game.players[opponents[i]].coins)
+            .unwrap_or(0)
+    };
+    let coins = game.players[player].coins;
+    let wager = match difficulty {
+        Difficulty::Easy => coins / 4,
+        Difficulty::Normal => coins / 3,
+        Difficulty::Hard => coins / 2,
+    };
+    (opponent, wager)
+}
+
+// what a bot wants its controller to do, axes point up and right like a stick
+#[derive(Copy, Clone, Default)]
+pub struct BotInput {
+    pub x: f32,
+    pub y: f32,
+    pub south: bool,
+}
+
+impl Difficulty {
+    // seconds between something happening in a minigame and a bot reacting
+    fn reaction_time(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.25,
+            Difficulty::Normal => 0.15,
+            Difficulty::Hard => 0.08,
+        }
+    }
+
+    // how far a bot's stick can drift from where it wants it
+    fn aim_error(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 0.3,
+            Difficulty::Hard => 0.1,
+        }
+    }
+
+    // accidental button presses per second
+    fn fumble_rate(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.15,
+            Difficulty::Normal => 0.05,
+            Difficulty::Hard => 0.0,
+        }
+    }
+}
+
+// turns a minigame's ideal input into what a CPU player of some difficulty does
+pub struct Bot {
+    pub player: usize,
+    pub id: usize,
+    difficulty: Difficulty,
+    time: f64,
+    seen: std::collections::VecDeque<(f64, BotInput)>,
+    drift: (f32, f32),
+}
+
+impl Bot {
+    pub fn new(player: usize, id: usize, difficulty: Difficulty) -> Self {
+        Bot {
+            player,
+            id,
+            difficulty,
+            time: 0.0,
+            seen: std::collections::VecDeque::new(),
+            drift: (0.0, 0.0),
+        }
+    }
+
+    pub fn update(&mut self, wanted: BotInput, dt: f64, rng: &mut game::GameRng) -> BotInput {
+        self.time += dt;
+        self.seen.push_back((self.time, wanted));
+        // act on what was wanted a reaction time ago
+        let reacted_at = self.time - self.difficulty.reaction_time();
+        while self.seen.len() > 1 && self.seen[1].0 <= reacted_at {
+            self.seen.pop_front();
+        }
+        let mut input = if self.seen[0].0 <= reacted_at {
+            self.seen[0].1
+        } else {
+            BotInput::default()
+        };
+
+        let error = self.difficulty.aim_error();
+        let step = dt as f32 * 4.0;
+        self.drift.0 = (self.drift.0 + rng.gen_range(-step, step)).clamp(-error, error);
+        self.drift.1 = (self.drift.1 + rng.gen_range(-step, step)).clamp(-error, error);
+        input.x = (input.x + self.drift.0).clamp(-1.0, 1.0);
+        input.y = (input.y + self.drift.1).clamp(-1.0, 1.0);
+        if rng.gen::<f64>() < self.difficulty.fumble_rate() * dt {
+            input.south = !input.south;
+        }
+        input
+    }
+}
@@ -0,0 +1,81 @@
+use crate::game;
+
+use rand::Rng;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Effect {
+    SwapCoins,
+    SwapPositions,
+    EveryoneLoses(u16),
+    EveryoneGains(u16),
+    GiveToEveryone(u16),
+    MoveStar,
+}
+
+impl Effect {
+    pub fn needs_target(self) -> bool {
+        matches!(self, Effect::SwapCoins | Effect::SwapPositions)
+    }
+}
+
+pub struct Event {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub weight: u32,
+    pub effect: Effect,
+}
+
+pub const EVENTS: [Event; 6] = [
+    Event {
+        name: "Coin Swap",
+        description: "Trade all your coins with another player.",
+        weight: 2,
+        effect: Effect::SwapCoins,
+    },
+    Event {
+        name: "Switcheroo",
+        description: "Trade places with another player.",
+        weight: 2,
+        effect: Effect::SwapPositions,
+    },
+    Event {
+        name: "Tax Day",
+        description: "Everyone loses 5 coins.",
+        weight: 3,
+        effect: Effect::EveryoneLoses(5),
+    },
+    Event {
+        name: "Windfall",
+        description: "Everyone gets 5 coins.",
+        weight: 3,
+        effect: Effect::EveryoneGains(5),
+    },
+    Event {
+        name: "Generosity",
+        description: "Give 3 coins to every other player.",
+        weight: 2,
+        effect: Effect::GiveToEveryone(3),
+    },
+    Event {
+        name: "Star Shuffle",
+        description: "The star moves somewhere else.",
+        weight: 1,
+        effect: Effect::MoveStar,
+    },
+];
+
+pub fn choose_event(player_count: usize, rng: &mut game::GameRng) -> &'static Event {
+    let possible: Vec<&Event> = EVENTS
+        .iter()
+        .filter(|event| player_count > 1 || !event.effect.needs_target())
+        .collect();
+    let total: u32 = possible.iter().map(|event| event.weight).sum();
+    let mut roll = rng.gen_range(0, total);
+    for event in &possible {
+        if roll < event.weight {
+            return event;
+        }
+        roll -= event.weight;
+    }
+    unreachable!()
+}
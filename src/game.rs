@@ -1,92 +1,150 @@
+use crate::render;
+use crate::replay;
+use crate::settings;
 use crate::tputil;
 use graphics::Transformed;
+use rand::SeedableRng;
+
+pub type GameRng = rand::rngs::StdRng;
+
+// the simulation always advances in steps of this length
+pub const TICK: f64 = 1.0 / 60.0;
+// skip time rather than trying to catch up after long stalls
+const MAX_FRAME_TIME: f64 = 0.25;
 
 pub struct App {
     pub input: tputil::InputState,
-    pub state: Box<dyn State>,
+    // overlays sit above the states they cover, only the top one is updated
+    stack: Vec<Box<dyn State>>,
     pub utils: Utils,
+    pub rng: GameRng,
+    pub recorder: Option<replay::Recorder>,
+    pub settings: settings::Settings,
+    // set once a state asks to leave the game
+    pub quit: bool,
+    accumulator: f64,
+    pause_held: bool,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(input: tputil::InputState, seed: u64) -> Self {
         Self {
-            input: tputil::InputState::new().unwrap(),
-            state: Box::new(crate::states::setup::MenuState {}),
-            utils: Utils {
-                font: opengl_graphics::GlyphCache::from_bytes(
-                    include_bytes!("../assets/fonts/OpenSans-Regular.ttf"),
-                    (),
-                    texture::TextureSettings::new(),
-                )
-                .expect("Failed to load font"),
-            },
+            input,
+            rng: GameRng::seed_from_u64(seed),
+            stack: vec![Box::new(crate::states::setup::MenuState::new())],
+            utils: Utils { alpha: 0.0 },
+            recorder: None,
+            settings: settings::Settings::default(),
+            quit: false,
+            accumulator: 0.0,
+            pause_held: false,
         }
     }
 
-    pub fn render(&mut self, c: graphics::Context, gl: &mut opengl_graphics::GlGraphics) {
+    // transform maps pixel coordinates of an area-sized surface onto the renderer
+    pub fn render(
+        &mut self,
+        transform: graphics::math::Matrix2d,
+        area: [u32; 2],
+        renderer: &mut dyn render::Renderer,
+    ) {
         const BGCOLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
 
-        let area = c.viewport.unwrap().draw_size;
         let scale = f64::from(std::cmp::min(area[0], area[1])) / 2.0;
 
-        graphics::clear(BGCOLOR, gl);
-        let transform = c
-            .transform
+        renderer.clear(BGCOLOR);
+        let transform = transform
             .trans(f64::from(area[0]) / 2.0, f64::from(area[1]) / 2.0)
             .scale(scale, scale);
-        self.state.render(gl, transform, &mut self.utils);
+        for state in &self.stack {
+            state.render(renderer, transform, &mut self.utils);
+        }
+    }
+
+    pub fn state(&self) -> &dyn State {
+        self.stack.last().unwrap().as_ref()
+    }
+
+    pub fn reset(&mut self, state: Box<dyn State>) {
+        self.stack = vec![state];
+    }
+
+    pub fn screenshot(&mut self, area: [u32; 2]) -> image::RgbaImage {
+        let mut renderer = render::software::SoftwareRenderer::new(area[0], area[1]);
+        self.render(graphics::math::identity(), area, &mut renderer);
+        renderer.into_image()
+    }
+
+    pub fn advance(&mut self, elapsed: f64) {
+        self.accumulator += elapsed.min(MAX_FRAME_TIME);
+        while self.accumulator >= TICK {
+            self.accumulator -= TICK;
+            self.update(TICK);
+        }
+        self.utils.alpha = self.accumulator / TICK;
     }
 
     pub fn update(&mut self, time: f64) {
         self.input.update();
-        let result = self.state.update(UpdateProps {
-            input: &self.input,
+        if let Some(ref mut recorder) = self.recorder {
+            if let Err(err) = recorder.record(time, &self.input) {
+                eprintln!("Failed to record input, recording stopped: {}", err);
+                self.recorder = None;
+            }
+        }
+        let pause = crate::states::pause::pause_pressed(&self.input);
+        let pause_pressed = pause && !self.pause_held;
+        self.pause_held = pause;
+        if pause_pressed && self.state().pausable() {
+            self.stack
+                .push(Box::new(crate::states::pause::PauseState::new()));
+            return;
+        }
+
+        let result = self.stack.last_mut().unwrap().update(UpdateProps {
+            input: &mut self.input,
+            rng: &mut self.rng,
+            settings: &mut self.settings,
             time,
         });
         match result {
             UpdateResult::Continue => {}
-            UpdateResult::NewState(new_state) => self.state = new_state,
+            UpdateResult::Quit => self.quit = true,
+            UpdateResult::NewState(new_state) => self.reset(new_state),
             UpdateResult::ToNewState(f) => {
-                struct UnreachableState;
-                impl State for UnreachableState {
-                    fn update(&mut self, _: UpdateProps<'_>) -> UpdateResult {
-                        unreachable!()
-                    }
-
-                    fn render(
-                        &self,
-                        _: &mut opengl_graphics::GlGraphics,
-                        _: graphics::math::Matrix2d,
-                        _: &mut Utils,
-                    ) {
-                        unreachable!()
-                    }
+                let old_state = self.stack.pop().unwrap();
+                self.stack.push(f(old_state));
+            }
+            UpdateResult::Push(state) => self.stack.push(state),
+            UpdateResult::Pop => {
+                if self.stack.len() > 1 {
+                    self.stack.pop();
                 }
-
-                let mut tmp: Box<dyn State + 'static> = Box::new(UnreachableState);
-                std::mem::swap(&mut self.state, &mut tmp);
-
-                let old_state = tmp;
-                let new_state = f(old_state);
-                self.state = new_state;
             }
         }
     }
 }
 
 pub struct UpdateProps<'a> {
-    pub input: &'a tputil::InputState,
+    pub input: &'a mut tputil::InputState,
+    pub rng: &'a mut GameRng,
+    pub settings: &'a mut settings::Settings,
     pub time: f64,
 }
 
 pub trait State: downcast_rs::Downcast {
-    fn render(
-        &self,
-        _: &mut opengl_graphics::GlGraphics,
-        _: graphics::math::Matrix2d,
-        _: &mut Utils,
-    );
+    fn render(&self, _: &mut dyn render::Renderer, _: graphics::math::Matrix2d, _: &mut Utils);
     fn update(&mut self, _: UpdateProps<'_>) -> UpdateResult;
+
+    // whether Start/Escape opens the pause menu over this state
+    fn pausable(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
 
 downcast_rs::impl_downcast!(State);
@@ -94,39 +152,46 @@ downcast_rs::impl_downcast!(State);
 #[must_use]
 pub enum UpdateResult {
     Continue,
+    // replaces every state, including the ones under overlays
     NewState(Box<dyn State>),
+    // replaces only the state that returned it
     ToNewState(Box<dyn FnOnce(Box<dyn State>) -> Box<dyn State>>),
+    // shows a state on top, the current one is kept but no longer updated
+    Push(Box<dyn State>),
+    // removes the state that returned it and resumes the one below
+    Pop,
+    Quit,
 }
 
 pub struct Utils {
-    pub font: opengl_graphics::GlyphCache<'static>,
+    // how far rendering is between the last tick and the next one, from 0 to 1
+    pub alpha: f64,
 }
 
 impl Utils {
-    pub fn draw_text(
-        &mut self,
-        text: &str,
-        text_size: f64,
-        trans: graphics::math::Matrix2d,
-        gl: &mut opengl_graphics::GlGraphics,
-    ) {
+    // the font size text_size is drawn at under trans, and how much smaller
+    // than trans the renderer's text units are
+    fn font_size(text_size: f64, trans: graphics::math::Matrix2d) -> (u32, f64) {
         let scale = graphics::math::get_scale(trans);
-        let scale = scale[0].max(scale[1]) * 576.0;
+        let scale = scale[0].max(scale[1]) * render::TEXT_SCALE;
 
         let scaled_text_size = text_size * scale;
         let rounded_text_size = scaled_text_size.ceil();
 
         let extra_scale = scaled_text_size / rounded_text_size;
 
-        graphics::Text::new(rounded_text_size as u32)
-            .draw(
-                text,
-                &mut self.font,
-                &Default::default(),
-                trans.scale(1.0 / scale * extra_scale, 1.0 / scale * extra_scale),
-                gl,
-            )
-            .unwrap();
+        (rounded_text_size as u32, 1.0 / scale * extra_scale)
+    }
+
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        text_size: f64,
+        trans: graphics::math::Matrix2d,
+        renderer: &mut dyn render::Renderer,
+    ) {
+        let (font_size, text_scale) = Utils::font_size(text_size, trans);
+        renderer.text(text, font_size, trans.scale(text_scale, text_scale));
     }
 
     pub fn draw_text_align(
@@ -135,14 +200,14 @@ impl Utils {
         text_size: f64,
         align: tputil::Alignment,
         trans: graphics::math::Matrix2d,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
     ) {
-        let width = self.text_width(text, text_size);
+        let width = self.text_width(text, text_size, trans, renderer);
         self.draw_text(
             text,
             text_size,
             align.align_text(trans, width, text_size / 1.33),
-            gl,
+            renderer,
         );
     }
 
@@ -153,15 +218,15 @@ impl Utils {
         align: tputil::Alignment,
         width: f64,
         trans: graphics::math::Matrix2d,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
     ) {
-        let space_width = self.text_width(" ", text_size);
+        let space_width = self.text_width(" ", text_size, trans, renderer);
 
         let mut lines = Vec::new();
         let mut current_line: Option<(usize, usize, f64)> = None;
         for (start, end, word) in tputil::str_split_ranges(text, ' ') {
             if let Some(ref mut current_line) = current_line {
-                let word_width = self.text_width(word, text_size);
+                let word_width = self.text_width(word, text_size, trans, renderer);
                 if current_line.2 + space_width + word_width > width {
                     lines.push(((current_line.0)..(current_line.1), current_line.2));
                     *current_line = (start, end, word_width);
@@ -173,7 +238,11 @@ impl Utils {
                     );
                 }
             } else {
-                current_line = Some((start, end, self.text_width(word, text_size)));
+                current_line = Some((
+                    start,
+                    end,
+                    self.text_width(word, text_size, trans, renderer),
+                ));
             }
         }
         {
@@ -181,22 +250,26 @@ impl Utils {
             lines.push(((last_line.0)..(last_line.1), last_line.2));
         }
 
-        println!("{:?}", lines);
-
         let text_trans = align.align_text_y(trans, text_size / 1.33);
         for (row, (range, line_width)) in lines.into_iter().rev().enumerate() {
             self.draw_text(
                 &text[range],
                 text_size,
                 align.align_x(text_trans.trans(0.0, -(row as f64) * text_size), line_width),
-                gl,
+                renderer,
             );
         }
     }
 
-    pub fn text_width(&mut self, text: &str, text_size: f64) -> f64 {
-        use graphics::character::CharacterCache;
-        let rounded = text_size.ceil();
-        self.font.width(rounded as u32, text).unwrap() * text_size / rounded * 1.33
+    pub fn text_width(
+        &mut self,
+        text: &str,
+        text_size: f64,
+        trans: graphics::math::Matrix2d,
+        renderer: &mut dyn render::Renderer,
+    ) -> f64 {
+        // measured at the size it would be drawn, as not every renderer scales text evenly
+        let (font_size, text_scale) = Utils::font_size(text_size, trans);
+        renderer.text_width(text, font_size) * text_scale * 1.33
     }
 }
@@ -0,0 +1,126 @@
+use crate::game;
+use crate::replay;
+use crate::states;
+use crate::tputil;
+
+use rand::{Rng, SeedableRng};
+
+const SCREENSHOT_SIZE: [u32; 2] = [600, 600];
+const BUTTONS: [tputil::Button; 6] = [
+    tputil::Button::DPadLeft,
+    tputil::Button::DPadRight,
+    tputil::Button::DPadUp,
+    tputil::Button::DPadDown,
+    tputil::Button::South,
+    tputil::Button::Start,
+];
+
+// mashes random keys on the keyboard player, enough to get through every screen
+struct Monkey {
+    rng: game::GameRng,
+}
+
+impl Monkey {
+    const TOGGLE_CHANCE: f64 = 0.2;
+
+    // Start is left alone where it would pause, since the pause menu
+    // leads to abandoning the party
+    fn step(&mut self, input: &mut tputil::InputState, pausable: bool) {
+        if self.rng.gen::<f64>() < Monkey::TOGGLE_CHANCE {
+            let button = BUTTONS[self.rng.gen_range(0, BUTTONS.len())];
+            let key = match input.bindings.keyboards.first().and_then(|l| l.key(button)) {
+                Some(key) => key,
+                None => return,
+            };
+            if input.is_key_pressed(key) {
+                input.on_key_release(key);
+            } else if !(pausable && button == tputil::Button::Start) {
+                input.on_key_press(key);
+            }
+        }
+    }
+}
+
+// returns whether a game was played to the end within max_ticks,
+// or whether the whole replay was played back if there is one
+pub fn run(
+    mut app: game::App,
+    seed: u64,
+    max_ticks: u64,
+    screenshots: Option<&std::path::Path>,
+    replay: Option<replay::Replay>,
+) -> bool {
+    let mut monkey = Monkey {
+        rng: game::GameRng::seed_from_u64(seed.wrapping_add(1)),
+    };
+    let mut frames = replay.map(|replay| replay.frames.into_iter());
+
+    let mut last_state = app.state().name();
+    println!("[{:8.2}] {}", 0.0, last_state);
+    let mut time = 0.0;
+    let mut tick = 0;
+    while frames.is_some() || tick < max_ticks {
+        tick += 1;
+        let dt = match frames {
+            Some(ref mut frames) => match frames.next() {
+                Some(frame) => {
+                    app.input.apply_snapshot(frame.input);
+                    frame.dt
+                }
+                None => {
+                    println!("Replay ended in {}", last_state);
+                    return true;
+                }
+            },
+            None => {
+                let pausable = app.state().pausable();
+                monkey.step(&mut app.input, pausable);
+                game::TICK
+            }
+        };
+        app.update(dt);
+        if app.quit {
+            if frames.is_some() {
+                println!("Replay quit the game");
+                return true;
+            }
+            // keep soaking instead of stopping when the monkey picks Quit
+            app.quit = false;
+            app.reset(Box::new(states::setup::MenuState::new()));
+        }
+
+        time += dt;
+        let state = app.state().name();
+        if state != last_state {
+            println!("[{:8.2}] {}", time, state);
+            last_state = state;
+            if let Some(dir) = screenshots {
+                let path = dir.join(format!("{:08}-{}.png", tick, state));
+                if let Err(err) = app.screenshot(SCREENSHOT_SIZE).save(&path) {
+                    eprintln!("Failed to save {}: {}", path.display(), err);
+                }
+            }
+        }
+
+        if let Some(results) = app
+            .state()
+            .downcast_ref::<states::ingame::FinalResultsState>()
+        {
+            let game = results.game();
+            for (rank, &index) in game.standings().iter().enumerate() {
+                let player = &game.players[index];
+                println!(
+                    "{}. {}: {} stars, {} coins",
+                    rank + 1,
+                    tputil::COLOR_NAMES[player.player.color],
+                    player.stars,
+                    player.coins
+                );
+            }
+            return true;
+        }
+    }
+
+    println!("Stopped after {} ticks in {}", max_ticks, last_state);
+    false
+}
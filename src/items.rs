@@ -0,0 +1,47 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Item {
+    DoubleDice,
+    SuperDice,
+    WarpToStar,
+    StealCoins,
+}
+
+pub const MAX_ITEMS: usize = 3;
+
+impl Item {
+    pub const ALL: [Item; 4] = [
+        Item::DoubleDice,
+        Item::SuperDice,
+        Item::WarpToStar,
+        Item::StealCoins,
+    ];
+
+    pub const STEAL_AMOUNT: u16 = 10;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Item::DoubleDice => "Double Dice",
+            Item::SuperDice => "Super Dice",
+            Item::WarpToStar => "Star Warp",
+            Item::StealCoins => "Pickpocket",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Item::DoubleDice => "Roll two dice and move the total.",
+            Item::SuperDice => "Roll a die that goes all the way up to 15.",
+            Item::WarpToStar => "Warp straight to the star instead of rolling.",
+            Item::StealCoins => "Steal 10 coins from the richest player before rolling.",
+        }
+    }
+
+    pub fn price(self) -> u16 {
+        match self {
+            Item::DoubleDice => 8,
+            Item::SuperDice => 8,
+            Item::WarpToStar => 20,
+            Item::StealCoins => 5,
+        }
+    }
+}
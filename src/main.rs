@@ -1,30 +1,208 @@
+mod bindings;
 mod board;
+mod cpu;
+mod events;
 mod game;
+mod headless;
+mod items;
+mod render;
+mod replay;
+mod save;
+mod settings;
 mod states;
 mod tputil;
+mod tui;
 
 use piston::input::{PressEvent, ReleaseEvent, RenderEvent, UpdateEvent};
+use rand::Rng;
 use sdl2_window::Sdl2Window as Window;
 
+const DEFAULT_HEADLESS_TICKS: u64 = 60 * 60 * 60;
+
+struct Options {
+    seed: Option<u64>,
+    headless: bool,
+    tui: bool,
+    ticks: u64,
+    screenshots: Option<std::path::PathBuf>,
+    record: Option<std::path::PathBuf>,
+    replay: Option<std::path::PathBuf>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        seed: None,
+        headless: false,
+        tui: false,
+        ticks: DEFAULT_HEADLESS_TICKS,
+        screenshots: None,
+        record: None,
+        replay: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().ok_or("--seed requires a value")?;
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed: {}", value))?,
+                );
+            }
+            "--headless" => options.headless = true,
+            "--tui" => options.tui = true,
+            "--ticks" => {
+                let value = args.next().ok_or("--ticks requires a value")?;
+                options.ticks = value
+                    .parse()
+                    .map_err(|_| format!("Invalid tick count: {}", value))?;
+            }
+            "--screenshots" => {
+                let value = args.next().ok_or("--screenshots requires a directory")?;
+                options.screenshots = Some(value.into());
+            }
+            "--record" => {
+                let value = args.next().ok_or("--record requires a file")?;
+                options.record = Some(value.into());
+            }
+            "--replay" => {
+                let value = args.next().ok_or("--replay requires a file")?;
+                options.replay = Some(value.into());
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    if options.tui && options.replay.is_some() {
+        return Err("--replay cannot be used with --tui".to_owned());
+    }
+    Ok(options)
+}
+
 fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: tuxparty [--seed <number>] [--record <file> | --replay <file>] \
+                 [--headless [--ticks <number>] [--screenshots <dir>] | --tui]"
+            );
+            std::process::exit(2);
+        }
+    };
+    let replay = options
+        .replay
+        .as_ref()
+        .map(|path| match replay::Replay::load(path) {
+            Ok(replay) => replay,
+            Err(err) => {
+                eprintln!("Failed to load replay {}: {}", path.display(), err);
+                std::process::exit(2);
+            }
+        });
+    let seed = match replay {
+        Some(ref replay) => replay.seed,
+        None => options.seed.unwrap_or_else(|| rand::thread_rng().gen()),
+    };
+    println!("Seed: {}", seed);
+
+    // simulated and recorded sessions must not depend on or clobber saved files
+    let persistent = !(options.headless || options.record.is_some() || replay.is_some());
+    if !persistent {
+        save::disable();
+    }
+
+    let input = if options.headless || options.tui || replay.is_some() {
+        tputil::InputState::without_gilrs()
+    } else {
+        tputil::InputState::new().unwrap_or_else(|err| {
+            eprintln!("Gamepads are unavailable, using the keyboard only: {}", err);
+            tputil::InputState::without_gilrs()
+        })
+    };
+    let mut app = game::App::new(input, seed);
+    if persistent {
+        app.settings = settings::Settings::load();
+        app.input.bindings = bindings::Bindings::load();
+    }
+    if let Some(ref path) = options.record {
+        match replay::Recorder::create(path, seed) {
+            Ok(recorder) => app.recorder = Some(recorder),
+            Err(err) => {
+                eprintln!("Failed to create {}: {}", path.display(), err);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if options.headless {
+        let finished = headless::run(
+            app,
+            seed,
+            options.ticks,
+            options.screenshots.as_deref(),
+            replay,
+        );
+        std::process::exit(if finished { 0 } else { 1 });
+    }
+    if options.tui {
+        if let Err(err) = tui::run(app) {
+            eprintln!("Terminal error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let gl_version = opengl_graphics::OpenGL::V2_1;
 
     let mut window: Window = piston::window::WindowSettings::new("Tuxparty", [600, 600])
         .opengl(gl_version)
-        .exit_on_esc(true)
+        .exit_on_esc(false)
         .srgb(false)
         .build()
         .unwrap();
 
     let mut gl = opengl_graphics::GlGraphics::new(gl_version);
 
-    let mut app = game::App::new();
+    let mut font = render::opengl::GlRenderer::load_font();
+
+    let mut frames = replay.map(|replay| replay.frames.into_iter());
+
+    let mut area = [600, 600];
 
     let mut events = piston::event_loop::Events::new(piston::event_loop::EventSettings::new());
 
     while let Some(e) = events.next(&mut window) {
+        if app.quit {
+            break;
+        }
         if let Some(r) = e.render_args() {
-            gl.draw(r.viewport(), |c, glo| app.render(c, glo));
+            area = [r.draw_width, r.draw_height];
+            gl.draw(r.viewport(), |c, glo| {
+                let mut renderer = render::opengl::GlRenderer::new(glo, &mut font);
+                app.render(c.transform, area, &mut renderer);
+            });
+        }
+        if let Some(ref mut frames) = frames {
+            if e.update_args().is_some() {
+                match frames.next() {
+                    Some(frame) => {
+                        app.input.apply_snapshot(frame.input);
+                        app.update(frame.dt);
+                    }
+                    None => {
+                        println!("Replay finished");
+                        break;
+                    }
+                }
+            }
+            if let Some(piston::input::Button::Keyboard(piston::input::keyboard::Key::F12)) =
+                e.press_args()
+            {
+                save_screenshot(&mut app, area);
+            }
+            continue;
         }
         if let Some(u) = e.update_args() {
             let multiplier = if app.input.is_key_pressed(piston::input::keyboard::Key::F4) {
@@ -32,9 +210,12 @@ fn main() {
             } else {
                 1.0
             };
-            app.update(u.dt * multiplier);
+            app.advance(u.dt * multiplier);
         }
         if let Some(piston::input::Button::Keyboard(key)) = e.press_args() {
+            if key == piston::input::keyboard::Key::F12 {
+                save_screenshot(&mut app, area);
+            }
             app.input.on_key_press(key);
         }
         if let Some(piston::input::Button::Keyboard(key)) = e.release_args() {
@@ -43,6 +224,18 @@ fn main() {
     }
 }
 
+fn save_screenshot(app: &mut game::App, area: [u32; 2]) {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let path = format!("screenshot-{}.png", time);
+    match app.screenshot(area).save(&path) {
+        Ok(()) => println!("Saved {}", path),
+        Err(err) => eprintln!("Failed to save {}: {}", path, err),
+    }
+}
+
 #[macro_export]
 macro_rules! to_new_state {
     ($e:expr) => {{
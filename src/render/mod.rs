@@ -0,0 +1,95 @@
+pub mod opengl;
+pub mod software;
+pub mod terminal;
+
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+
+pub const FONT: &[u8] = include_bytes!("../../assets/fonts/OpenSans-Regular.ttf");
+// font sizes are in units this many times smaller than a pixel of the surface
+pub const TEXT_SCALE: f64 = 576.0;
+
+pub trait Renderer {
+    fn clear(&mut self, color: Color);
+    fn rectangle(&mut self, color: Color, rect: [f64; 4], trans: Matrix2d);
+    fn line(&mut self, color: Color, radius: f64, line: [f64; 4], trans: Matrix2d);
+    // text is drawn black with its baseline at the origin of trans
+    fn text(&mut self, text: &str, font_size: u32, trans: Matrix2d);
+    fn text_width(&mut self, text: &str, font_size: u32) -> f64;
+}
+
+pub fn transform(trans: Matrix2d, x: f64, y: f64) -> [f64; 2] {
+    [
+        trans[0][0] * x + trans[0][1] * y + trans[0][2],
+        trans[1][0] * x + trans[1][1] * y + trans[1][2],
+    ]
+}
+
+fn rectangle_points(rect: [f64; 4], trans: Matrix2d) -> [[f64; 2]; 4] {
+    let [x, y, w, h] = rect;
+    [
+        transform(trans, x, y),
+        transform(trans, x + w, y),
+        transform(trans, x + w, y + h),
+        transform(trans, x, y + h),
+    ]
+}
+
+// a line is a rectangle around the segment, extended by radius at both ends
+fn line_points(radius: f64, line: [f64; 4], trans: Matrix2d) -> Option<[[f64; 2]; 4]> {
+    let [x1, y1, x2, y2] = line;
+    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    if length == 0.0 {
+        return None;
+    }
+    let dx = (x2 - x1) / length * radius;
+    let dy = (y2 - y1) / length * radius;
+    Some([
+        transform(trans, x1 - dx + dy, y1 - dy - dx),
+        transform(trans, x2 + dx + dy, y2 + dy - dx),
+        transform(trans, x2 + dx - dy, y2 + dy + dx),
+        transform(trans, x1 - dx - dy, y1 - dy + dx),
+    ])
+}
+
+// calls plot for every pixel whose center lies inside the convex polygon
+fn fill_convex<F: FnMut(u32, u32)>(points: &[[f64; 2]], width: u32, height: u32, mut plot: F) {
+    let min_x = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+    let max_x = points
+        .iter()
+        .map(|p| p[0])
+        .fold(f64::NEG_INFINITY, f64::max);
+    let min_y = points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+    let max_y = points
+        .iter()
+        .map(|p| p[1])
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let start_x = min_x.floor().max(0.0) as u32;
+    let end_x = max_x.ceil().min(f64::from(width)).max(0.0) as u32;
+    let start_y = min_y.floor().max(0.0) as u32;
+    let end_y = max_y.ceil().min(f64::from(height)).max(0.0) as u32;
+
+    for y in start_y..end_y {
+        for x in start_x..end_x {
+            if contains(points, [f64::from(x) + 0.5, f64::from(y) + 0.5]) {
+                plot(x, y);
+            }
+        }
+    }
+}
+
+fn contains(points: &[[f64; 2]], point: [f64; 2]) -> bool {
+    let mut positive = false;
+    let mut negative = false;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let cross = (b[0] - a[0]) * (point[1] - a[1]) - (b[1] - a[1]) * (point[0] - a[0]);
+        if cross > 0.0 {
+            positive = true;
+        } else if cross < 0.0 {
+            negative = true;
+        }
+    }
+    !(positive && negative)
+}
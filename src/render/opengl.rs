@@ -0,0 +1,47 @@
+use super::Renderer;
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+
+pub struct GlRenderer<'a> {
+    gl: &'a mut opengl_graphics::GlGraphics,
+    font: &'a mut opengl_graphics::GlyphCache<'static>,
+}
+
+impl<'a> GlRenderer<'a> {
+    pub fn new(
+        gl: &'a mut opengl_graphics::GlGraphics,
+        font: &'a mut opengl_graphics::GlyphCache<'static>,
+    ) -> Self {
+        GlRenderer { gl, font }
+    }
+
+    pub fn load_font() -> opengl_graphics::GlyphCache<'static> {
+        opengl_graphics::GlyphCache::from_bytes(super::FONT, (), texture::TextureSettings::new())
+            .expect("Failed to load font")
+    }
+}
+
+impl<'a> Renderer for GlRenderer<'a> {
+    fn clear(&mut self, color: Color) {
+        graphics::clear(color, self.gl);
+    }
+
+    fn rectangle(&mut self, color: Color, rect: [f64; 4], trans: Matrix2d) {
+        graphics::rectangle(color, rect, trans, self.gl);
+    }
+
+    fn line(&mut self, color: Color, radius: f64, line: [f64; 4], trans: Matrix2d) {
+        graphics::line(color, radius, line, trans, self.gl);
+    }
+
+    fn text(&mut self, text: &str, font_size: u32, trans: Matrix2d) {
+        graphics::Text::new(font_size)
+            .draw(text, self.font, &Default::default(), trans, self.gl)
+            .unwrap();
+    }
+
+    fn text_width(&mut self, text: &str, font_size: u32) -> f64 {
+        use graphics::character::CharacterCache;
+        self.font.width(font_size, text).unwrap()
+    }
+}
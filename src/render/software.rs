@@ -0,0 +1,164 @@
+use super::Renderer;
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+
+// rasterizes on the CPU, with no antialiasing except for text
+pub struct SoftwareRenderer {
+    image: image::RgbaImage,
+    font: rusttype::Font<'static>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            image: image::RgbaImage::new(width, height),
+            font: rusttype::Font::from_bytes(super::FONT).expect("Failed to load font"),
+        }
+    }
+
+    pub fn into_image(self) -> image::RgbaImage {
+        self.image
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let alpha = color[3] * coverage;
+        let pixel = self.image.get_pixel_mut(x, y);
+        for (channel, &value) in pixel.data.iter_mut().zip(color.iter()).take(3) {
+            let old = f32::from(*channel) / 255.0;
+            *channel = ((value * alpha + old * (1.0 - alpha)) * 255.0).round() as u8;
+        }
+        let old = f32::from(pixel.data[3]) / 255.0;
+        pixel.data[3] = ((alpha + old * (1.0 - alpha)) * 255.0).round() as u8;
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn clear(&mut self, color: Color) {
+        let pixel = image::Rgba([
+            (color[0] * 255.0).round() as u8,
+            (color[1] * 255.0).round() as u8,
+            (color[2] * 255.0).round() as u8,
+            (color[3] * 255.0).round() as u8,
+        ]);
+        for p in self.image.pixels_mut() {
+            *p = pixel;
+        }
+    }
+
+    fn rectangle(&mut self, color: Color, rect: [f64; 4], trans: Matrix2d) {
+        let (width, height) = self.image.dimensions();
+        super::fill_convex(
+            &super::rectangle_points(rect, trans),
+            width,
+            height,
+            |x, y| self.blend(x, y, color, 1.0),
+        );
+    }
+
+    fn line(&mut self, color: Color, radius: f64, line: [f64; 4], trans: Matrix2d) {
+        let (width, height) = self.image.dimensions();
+        if let Some(points) = super::line_points(radius, line, trans) {
+            super::fill_convex(&points, width, height, |x, y| self.blend(x, y, color, 1.0));
+        }
+    }
+
+    fn text(&mut self, text: &str, font_size: u32, trans: Matrix2d) {
+        const COLOR: Color = [0.0, 0.0, 0.0, 1.0];
+
+        // glyphs are rasterized at output resolution rather than transformed
+        let scale = graphics::math::get_scale(trans)[0];
+        let origin = super::transform(trans, 0.0, 0.0);
+        let size = rusttype::Scale::uniform((f64::from(font_size) * scale) as f32);
+        let (width, height) = self.image.dimensions();
+
+        let mut x = origin[0] as f32;
+        let glyphs: Vec<_> = text
+            .chars()
+            .map(|ch| {
+                let glyph = self.font.glyph(ch).scaled(size);
+                let advance = glyph.h_metrics().advance_width;
+                let positioned = glyph.positioned(rusttype::point(x, origin[1] as f32));
+                x += advance;
+                positioned
+            })
+            .collect();
+        for glyph in glyphs {
+            if let Some(bounds) = glyph.pixel_bounding_box() {
+                glyph.draw(|gx, gy, coverage| {
+                    let px = bounds.min.x + gx as i32;
+                    let py = bounds.min.y + gy as i32;
+                    if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                        self.blend(px as u32, py as u32, COLOR, coverage);
+                    }
+                });
+            }
+        }
+    }
+
+    fn text_width(&mut self, text: &str, font_size: u32) -> f64 {
+        let size = rusttype::Scale::uniform(font_size as f32);
+        text.chars()
+            .map(|ch| f64::from(self.font.glyph(ch).scaled(size).h_metrics().advance_width))
+            .sum()
+    }
+}
+
+// draws into an image the way App::render does and compares it with
+// tests/golden/<name>.png, rewriting that file when UPDATE_GOLDEN is set
+#[cfg(test)]
+pub fn assert_golden<F: FnOnce(&mut dyn Renderer, Matrix2d)>(name: &str, draw: F) {
+    use graphics::Transformed;
+
+    const SIZE: u32 = 256;
+    // leaves room for rounding differences in font rasterization
+    const TOLERANCE: u8 = 2;
+
+    let mut renderer = SoftwareRenderer::new(SIZE, SIZE);
+    renderer.clear([1.0, 1.0, 1.0, 1.0]);
+    let half = f64::from(SIZE) / 2.0;
+    draw(
+        &mut renderer,
+        graphics::math::identity()
+            .trans(half, half)
+            .scale(half, half),
+    );
+    let actual = renderer.into_image();
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+    let expected = match image::open(&path) {
+        Ok(expected) => expected.to_rgba(),
+        Err(err) => panic!(
+            "Failed to open {} ({}), run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        ),
+    };
+    assert_eq!(actual.dimensions(), expected.dimensions());
+    let differing = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, b)| {
+            a.data
+                .iter()
+                .zip(b.data.iter())
+                .any(|(&a, &b)| (i16::from(a) - i16::from(b)).abs() > i16::from(TOLERANCE))
+        })
+        .count();
+    if differing > 0 {
+        let actual_path = std::env::temp_dir().join(format!("{}.png", name));
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{} pixels differ from {}, got {}",
+            differing,
+            path.display(),
+            actual_path.display()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use super::Renderer;
+use crossterm::style::{self, Color as TermColor};
+use crossterm::{cursor, queue};
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+
+// each terminal cell shows two square-ish pixels stacked with a half block character
+pub struct TerminalRenderer {
+    columns: u16,
+    rows: u16,
+    pixels: Vec<[f32; 3]>,
+    text: Vec<Option<char>>,
+}
+
+impl TerminalRenderer {
+    pub fn new(columns: u16, rows: u16) -> Self {
+        let cells = usize::from(columns) * usize::from(rows);
+        TerminalRenderer {
+            columns,
+            rows,
+            pixels: vec![[0.0; 3]; cells * 2],
+            text: vec![None; cells],
+        }
+    }
+
+    pub fn area(&self) -> [u32; 2] {
+        [u32::from(self.columns), u32::from(self.rows) * 2]
+    }
+
+    fn pixel(&self, x: u16, y: u16) -> [f32; 3] {
+        self.pixels[usize::from(y) * usize::from(self.columns) + usize::from(x)]
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Color) {
+        let index = y as usize * usize::from(self.columns) + x as usize;
+        let pixel = &mut self.pixels[index];
+        for (channel, &value) in pixel.iter_mut().zip(color.iter()) {
+            *channel = value * color[3] + *channel * (1.0 - color[3]);
+        }
+    }
+
+    fn to_term(color: [f32; 3]) -> TermColor {
+        TermColor::Rgb {
+            r: (color[0] * 255.0).round() as u8,
+            g: (color[1] * 255.0).round() as u8,
+            b: (color[2] * 255.0).round() as u8,
+        }
+    }
+
+    pub fn present<W: std::io::Write>(&self, out: &mut W) -> crossterm::Result<()> {
+        for row in 0..self.rows {
+            queue!(out, cursor::MoveTo(0, row))?;
+            for column in 0..self.columns {
+                let top = self.pixel(column, row * 2);
+                let bottom = self.pixel(column, row * 2 + 1);
+                let cell = usize::from(row) * usize::from(self.columns) + usize::from(column);
+                match self.text[cell] {
+                    Some(ch) => {
+                        let luminance = 0.3 * top[0] + 0.6 * top[1] + 0.1 * top[2];
+                        let fg = if luminance > 0.5 {
+                            TermColor::Black
+                        } else {
+                            TermColor::White
+                        };
+                        queue!(
+                            out,
+                            style::SetForegroundColor(fg),
+                            style::SetBackgroundColor(TerminalRenderer::to_term(top)),
+                            style::Print(ch)
+                        )?;
+                    }
+                    None => queue!(
+                        out,
+                        style::SetForegroundColor(TerminalRenderer::to_term(top)),
+                        style::SetBackgroundColor(TerminalRenderer::to_term(bottom)),
+                        style::Print('▀')
+                    )?,
+                }
+            }
+        }
+        queue!(out, style::ResetColor)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn clear(&mut self, color: Color) {
+        for pixel in &mut self.pixels {
+            *pixel = [color[0], color[1], color[2]];
+        }
+        for cell in &mut self.text {
+            *cell = None;
+        }
+    }
+
+    fn rectangle(&mut self, color: Color, rect: [f64; 4], trans: Matrix2d) {
+        let [width, height] = self.area();
+        super::fill_convex(
+            &super::rectangle_points(rect, trans),
+            width,
+            height,
+            |x, y| self.blend(x, y, color),
+        );
+    }
+
+    fn line(&mut self, color: Color, radius: f64, line: [f64; 4], trans: Matrix2d) {
+        let [width, height] = self.area();
+        if let Some(points) = super::line_points(radius, line, trans) {
+            super::fill_convex(&points, width, height, |x, y| self.blend(x, y, color));
+        }
+    }
+
+    // text is usually smaller than a cell, so a line that would land on cells
+    // already holding text moves down until it fits
+    fn text(&mut self, text: &str, _font_size: u32, trans: Matrix2d) {
+        let origin = super::transform(trans, 0.0, 0.0);
+        let columns = i64::from(self.columns);
+        let start = origin[0].round() as i64;
+        let chars: Vec<(i64, char)> = text
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| (start + i as i64, ch))
+            .filter(|&(column, _)| column >= 0 && column < columns)
+            .collect();
+
+        let mut row = ((origin[1] - 1.0) / 2.0).floor().max(0.0) as i64;
+        while row < i64::from(self.rows) {
+            let base = row * columns;
+            if chars
+                .iter()
+                .all(|&(column, _)| self.text[(base + column) as usize].is_none())
+            {
+                for &(column, ch) in &chars {
+                    self.text[(base + column) as usize] = Some(ch);
+                }
+                return;
+            }
+            row += 1;
+        }
+    }
+
+    // one cell per character whatever the size, less the third that
+    // Utils::text_width adds on top of every width
+    fn text_width(&mut self, text: &str, _font_size: u32) -> f64 {
+        text.chars().count() as f64 * super::TEXT_SCALE / 1.33
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::Transformed;
+
+    #[test]
+    fn text_is_measured_in_cells() {
+        let mut renderer = TerminalRenderer::new(80, 24);
+        let trans = graphics::math::identity().scale(24.0, 24.0);
+        let mut utils = crate::game::Utils { alpha: 0.0 };
+        for &size in &[0.05, 0.1, 0.3] {
+            let width = utils.text_width("Hello", size, trans, &mut renderer) * 24.0;
+            assert!(
+                (width - 5.0).abs() < 0.01,
+                "{} cells wide at {}",
+                width,
+                size
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::tputil;
+
+use std::io::{BufRead, Write};
+
+// a replay file is JSON lines: a header, then one frame per update
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Header {
+    seed: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Frame {
+    pub dt: f64,
+    pub input: tputil::InputSnapshot,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Empty,
+    Syntax {
+        line: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::Empty => write!(f, "file is empty"),
+            ReplayError::Syntax { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+pub struct Recorder {
+    out: std::io::BufWriter<std::fs::File>,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<std::path::Path>>(path: P, seed: u64) -> std::io::Result<Self> {
+        let mut recorder = Recorder {
+            out: std::io::BufWriter::new(std::fs::File::create(path)?),
+        };
+        recorder.write_line(&Header { seed })?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, dt: f64, input: &tputil::InputState) -> std::io::Result<()> {
+        self.write_line(&Frame {
+            dt,
+            input: input.snapshot(),
+        })
+    }
+
+    fn write_line<T: serde::Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.out, value)?;
+        self.out.write_all(b"\n")
+    }
+}
+
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<Frame>,
+}
+
+impl Replay {
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ReplayError> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let mut lines = file.lines().enumerate();
+
+        let header: Header = match lines.next() {
+            Some((_, line)) => serde_json::from_str(&line?)
+                .map_err(|error| ReplayError::Syntax { line: 1, error })?,
+            None => return Err(ReplayError::Empty),
+        };
+        let mut frames = Vec::new();
+        for (index, line) in lines {
+            let frame = serde_json::from_str(&line?).map_err(|error| ReplayError::Syntax {
+                line: index + 1,
+                error,
+            })?;
+            frames.push(frame);
+        }
+
+        Ok(Replay {
+            seed: header.seed,
+            frames,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::Key;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("tuxparty-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    fn pad(south: bool) -> tputil::GamepadSnapshot {
+        let mut axes = [0.0; 8];
+        let x = tputil::GAMEPAD_AXES
+            .iter()
+            .position(|&axis| axis == gilrs::Axis::LeftStickX)
+            .unwrap();
+        axes[x] = -0.5;
+        let south = tputil::GAMEPAD_BUTTONS
+            .iter()
+            .position(|&button| button == tputil::Button::South)
+            .filter(|_| south);
+        tputil::GamepadSnapshot {
+            id: 7,
+            buttons: south.map_or(0, |i| 1 << i),
+            axes,
+        }
+    }
+
+    #[test]
+    fn recorded_input_replays() {
+        let path = temp_path("round-trip");
+        let mut input = tputil::InputState::without_gilrs();
+        // bots are driven again on replay, so their pads aren't recorded
+        if let tputil::InputMethod::Virtual(bot) = input.new_virtual() {
+            input.set_virtual_button(bot, tputil::Button::South, true);
+        }
+        {
+            let mut recorder = Recorder::create(&path, 42).unwrap();
+            input.apply_snapshot(tputil::InputSnapshot {
+                keys: vec![Key::Up],
+                gamepads: vec![pad(true)],
+            });
+            recorder.record(0.25, &input).unwrap();
+            input.apply_snapshot(tputil::InputSnapshot {
+                keys: Vec::new(),
+                gamepads: vec![pad(false)],
+            });
+            recorder.record(0.5, &input).unwrap();
+        }
+        let replay = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let replay = replay.unwrap();
+        assert_eq!(replay.seed, 42);
+        let dts: Vec<f64> = replay.frames.iter().map(|frame| frame.dt).collect();
+        assert_eq!(dts, vec![0.25, 0.5]);
+        assert!(replay
+            .frames
+            .iter()
+            .all(|frame| frame.input.gamepads.len() == 1));
+
+        let pad = tputil::InputMethod::Virtual(0);
+        let mut replayed = tputil::InputState::without_gilrs();
+        let mut frames = replay.frames.into_iter();
+        replayed.apply_snapshot(frames.next().unwrap().input);
+        assert!(replayed.is_key_pressed(Key::Up));
+        assert!(replayed.is_pressed(&pad, tputil::Button::South));
+        assert!((replayed.get_axis(&pad, tputil::Axis::X) + 0.5).abs() < 1e-6);
+        replayed.apply_snapshot(frames.next().unwrap().input);
+        assert!(!replayed.is_key_pressed(Key::Up));
+        assert!(!replayed.is_pressed(&pad, tputil::Button::South));
+        assert!((replayed.get_axis(&pad, tputil::Axis::X) + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_reports_bad_files() {
+        let path = temp_path("bad");
+        std::fs::write(&path, "").unwrap();
+        let empty = Replay::load(&path);
+        std::fs::write(
+            &path,
+            "{\"seed\":1}\n{\"dt\":0.1,\"input\":{\"keys\":[],\"gamepads\":[]}}\nnope\n",
+        )
+        .unwrap();
+        let broken = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        match empty {
+            Err(ReplayError::Empty) => {}
+            other => panic!("expected an empty file error, got {:?}", other.err()),
+        }
+        match broken {
+            Err(ReplayError::Syntax { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected a syntax error, got {:?}", other.err()),
+        }
+    }
+}
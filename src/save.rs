@@ -0,0 +1,95 @@
+use crate::states::ingame::GameInfo;
+use crate::tputil;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const SAVE_PATH: &str = "savegame.json";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Syntax(serde_json::Error),
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Syntax(err) => write!(f, "{}", err),
+            SaveError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+// used by simulated runs, which shouldn't touch the player's saved party
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn exists() -> bool {
+    ENABLED.load(Ordering::Relaxed) && std::path::Path::new(SAVE_PATH).exists()
+}
+
+pub fn save(game: &GameInfo) -> Result<(), SaveError> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    // write the whole file before replacing the old save
+    let temp_path = format!("{}.tmp", SAVE_PATH);
+    let file = std::io::BufWriter::new(std::fs::File::create(&temp_path)?);
+    serde_json::to_writer(file, game).map_err(SaveError::Syntax)?;
+    std::fs::rename(temp_path, SAVE_PATH)?;
+    Ok(())
+}
+
+pub fn autosave(game: &GameInfo) {
+    if let Err(err) = save(game) {
+        eprintln!("Failed to save game: {}", err);
+    }
+}
+
+pub fn load() -> Result<GameInfo, SaveError> {
+    let file = std::io::BufReader::new(std::fs::File::open(SAVE_PATH)?);
+    let game: GameInfo = serde_json::from_reader(file).map_err(SaveError::Syntax)?;
+
+    if game.players.is_empty() {
+        return Err(SaveError::Invalid("no players"));
+    }
+    if game
+        .players
+        .iter()
+        .any(|player| player.player.color >= tputil::COLORS.len())
+    {
+        return Err(SaveError::Invalid("invalid player color"));
+    }
+    if game
+        .players
+        .iter()
+        .any(|player| game.map.get_space(player.space).is_none())
+        || game.map.get_space(game.star_space).is_none()
+    {
+        return Err(SaveError::Invalid("board no longer matches the saved game"));
+    }
+    Ok(game)
+}
+
+pub fn delete() {
+    if ENABLED.load(Ordering::Relaxed) {
+        if let Err(err) = std::fs::remove_file(SAVE_PATH) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Failed to remove {}: {}", SAVE_PATH, err);
+            }
+        }
+    }
+}
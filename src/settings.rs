@@ -0,0 +1,54 @@
+use crate::cpu;
+
+pub const SETTINGS_PATH: &str = "settings.json";
+
+pub const MIN_TURNS: u16 = 1;
+pub const MAX_TURNS: u16 = 50;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub turns: u16,
+    pub bonus_stars: bool,
+    pub cpu_difficulty: cpu::Difficulty,
+    // where changes are written, None for sessions that shouldn't persist them
+    #[serde(skip)]
+    path: Option<std::path::PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            turns: 10,
+            bonus_stars: true,
+            cpu_difficulty: cpu::Difficulty::Normal,
+            path: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        let mut settings = match std::fs::read_to_string(SETTINGS_PATH) {
+            Ok(src) => serde_json::from_str(&src).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid {}: {}", SETTINGS_PATH, err);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+        settings.turns = settings.turns.clamp(MIN_TURNS, MAX_TURNS);
+        settings.path = Some(SETTINGS_PATH.into());
+        settings
+    }
+
+    pub fn save(&self) {
+        if let Some(ref path) = self.path {
+            let result = serde_json::to_string_pretty(self)
+                .map_err(std::io::Error::from)
+                .and_then(|src| std::fs::write(path, src));
+            if let Err(err) = result {
+                eprintln!("Failed to save {}: {}", path.display(), err);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use graphics::Transformed;
+
+struct Bonus {
+    name: &'static str,
+    description: &'static str,
+    stat: fn(&states::ingame::PlayerStats) -> u16,
+}
+
+const BONUSES: [Bonus; 4] = [
+    Bonus {
+        name: "Minigame Star",
+        description: "For winning the most coins in minigames",
+        stat: |stats| stats.minigame_coins,
+    },
+    Bonus {
+        name: "Coin Star",
+        description: "For collecting the most coins",
+        stat: |stats| stats.coins_gained,
+    },
+    Bonus {
+        name: "Running Star",
+        description: "For moving the most spaces",
+        stat: |stats| stats.spaces_moved,
+    },
+    Bonus {
+        name: "Unlucky Star",
+        description: "For landing on the most red spaces",
+        stat: |stats| stats.negative_spaces,
+    },
+];
+
+pub struct BonusStarState {
+    game: states::ingame::GameInfo,
+    bonus: usize,
+    winners: Vec<usize>,
+    time: f64,
+}
+
+impl BonusStarState {
+    const REVEAL_TIME: f64 = 2.0;
+    const NEXT_TIME: f64 = 4.5;
+
+    pub fn new(game: states::ingame::GameInfo) -> Self {
+        BonusStarState::for_bonus(game, 0)
+    }
+
+    fn for_bonus(game: states::ingame::GameInfo, bonus: usize) -> Self {
+        let winners = BonusStarState::find_winners(&game, &BONUSES[bonus]);
+        BonusStarState {
+            game,
+            bonus,
+            winners,
+            time: 0.0,
+        }
+    }
+
+    fn find_winners(game: &states::ingame::GameInfo, bonus: &Bonus) -> Vec<usize> {
+        let values: Vec<_> = game
+            .players
+            .iter()
+            .map(|player| (bonus.stat)(&player.stats))
+            .collect();
+        let best = values.iter().cloned().max().unwrap_or(0);
+        if best == 0 {
+            return vec![];
+        }
+        values
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, value)| value == best)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl game::State for BonusStarState {
+    fn pausable(&self) -> bool {
+        true
+    }
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        let bonus = &BONUSES[self.bonus];
+        utils.draw_text_align(
+            "Bonus Stars",
+            0.15,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -1.0),
+            renderer,
+        );
+        utils.draw_text_align(
+            bonus.name,
+            0.1,
+            tputil::Alignment::MIDDLE_CENTER,
+            trans.trans(0.0, -0.4),
+            renderer,
+        );
+        utils.draw_text_align(
+            bonus.description,
+            0.05,
+            tputil::Alignment::MIDDLE_CENTER,
+            trans.trans(0.0, -0.25),
+            renderer,
+        );
+
+        if self.time < BonusStarState::REVEAL_TIME {
+            return;
+        }
+
+        if self.winners.is_empty() {
+            utils.draw_text_align(
+                "Nobody!",
+                0.1,
+                tputil::Alignment::MIDDLE_CENTER,
+                trans.trans(0.0, 0.2),
+                renderer,
+            );
+            return;
+        }
+
+        let scale = 2.0 / (self.winners.len() + 1) as f64;
+        for (i, &index) in self.winners.iter().enumerate() {
+            let player = &self.game.players[index];
+            let x = scale * (i as f64 + 1.0) - 1.0;
+            renderer.rectangle(
+                tputil::COLORS[player.player.color],
+                graphics::rectangle::centered_square(x, 0.2, 0.1),
+                trans,
+            );
+            utils.draw_text_align(
+                &(bonus.stat)(&player.stats).to_string(),
+                0.07,
+                tputil::Alignment::TOP_CENTER,
+                trans.trans(x, 0.35),
+                renderer,
+            );
+        }
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        self.time += props.time;
+        if self.time > BonusStarState::NEXT_TIME {
+            for &index in &self.winners {
+                self.game.players[index].stars += 1;
+            }
+            if self.bonus + 1 < BONUSES.len() {
+                return crate::to_new_state!(|prev: Self| {
+                    Box::new(BonusStarState::for_bonus(prev.game, prev.bonus + 1))
+                });
+            } else {
+                return crate::to_new_state!(|prev: Self| {
+                    Box::new(states::ingame::FinalResultsState::new(prev.game))
+                });
+            }
+        }
+
+        game::UpdateResult::Continue
+    }
+}
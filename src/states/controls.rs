@@ -0,0 +1,344 @@
+use crate::bindings;
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use piston::input::Key;
+
+#[derive(Clone, Copy)]
+enum Row {
+    Button(tputil::Button),
+    Axis(tputil::Axis),
+    Invert(tputil::Axis),
+    Deadzone,
+    Reset,
+    Back,
+}
+
+fn button_label(button: tputil::Button) -> &'static str {
+    match button {
+        tputil::Button::DPadUp => "Up",
+        tputil::Button::DPadDown => "Down",
+        tputil::Button::DPadLeft => "Left",
+        tputil::Button::DPadRight => "Right",
+        _ => bindings::button_name(button),
+    }
+}
+
+fn axis_label(axis: tputil::Axis) -> &'static str {
+    match axis {
+        tputil::Axis::X => "horizontal",
+        tputil::Axis::Y => "vertical",
+    }
+}
+
+pub struct ControlsState {
+    bindings: bindings::Bindings,
+    // the keyboard layout or gamepad being set up, once someone picked one
+    device: Option<tputil::InputMethod>,
+    gamepad_name: String,
+    menu: states::menu::Menu,
+    // the row waiting for a new key, button or axis
+    waiting: Option<Row>,
+    released: bool,
+    // keys already down when waiting started, which don't count until pressed again
+    held_keys: Vec<Key>,
+}
+
+impl ControlsState {
+    pub fn new(bindings: bindings::Bindings) -> Self {
+        ControlsState {
+            bindings,
+            device: None,
+            gamepad_name: String::new(),
+            menu: states::menu::Menu::new(),
+            waiting: None,
+            released: false,
+            held_keys: Vec::new(),
+        }
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        match self.device {
+            Some(tputil::InputMethod::Keyboard(_)) => {
+                for &button in &[
+                    tputil::Button::DPadUp,
+                    tputil::Button::DPadDown,
+                    tputil::Button::DPadLeft,
+                    tputil::Button::DPadRight,
+                ] {
+                    rows.push(Row::Button(button));
+                }
+                rows.extend(bindings::GAME_BUTTONS.iter().map(|&b| Row::Button(b)));
+            }
+            Some(tputil::InputMethod::Gamepad(_)) => {
+                rows.extend(bindings::GAME_BUTTONS.iter().map(|&b| Row::Button(b)));
+                rows.push(Row::Axis(tputil::Axis::X));
+                rows.push(Row::Axis(tputil::Axis::Y));
+                rows.push(Row::Invert(tputil::Axis::X));
+                rows.push(Row::Invert(tputil::Axis::Y));
+                rows.push(Row::Deadzone);
+            }
+            _ => {}
+        }
+        rows.push(Row::Reset);
+        rows.push(Row::Back);
+        rows
+    }
+
+    fn keyboard(&self) -> Option<&bindings::KeyLayout> {
+        match self.device {
+            Some(tputil::InputMethod::Keyboard(slot)) => self.bindings.keyboards.get(slot),
+            _ => None,
+        }
+    }
+
+    fn gamepad(&self) -> &bindings::GamepadBindings {
+        self.bindings.gamepad(&self.gamepad_name)
+    }
+
+    fn choice(&self, row: Row) -> String {
+        match row {
+            Row::Button(button) => {
+                let bound = match self.keyboard() {
+                    Some(layout) => match layout.key(button) {
+                        Some(key) => format!("{:?}", key),
+                        None => "None".to_owned(),
+                    },
+                    None => bindings::button_name(self.gamepad().button(button)).to_owned(),
+                };
+                format!("{}: {}", button_label(button), bound)
+            }
+            Row::Axis(axis) => {
+                let axes = match axis {
+                    tputil::Axis::X => &self.gamepad().x_axes,
+                    tputil::Axis::Y => &self.gamepad().y_axes,
+                };
+                let names: Vec<&str> = axes.iter().map(|&a| bindings::axis_name(a)).collect();
+                format!("Move {}: {}", axis_label(axis), names.join(" + "))
+            }
+            Row::Invert(axis) => {
+                let inverted = match axis {
+                    tputil::Axis::X => self.gamepad().invert_x,
+                    tputil::Axis::Y => self.gamepad().invert_y,
+                };
+                format!(
+                    "Invert {}: {}",
+                    axis_label(axis),
+                    if inverted { "On" } else { "Off" }
+                )
+            }
+            Row::Deadzone => format!("Deadzone: {:.2}", self.gamepad().deadzone),
+            Row::Reset => "Reset to defaults".to_owned(),
+            Row::Back => "Back".to_owned(),
+        }
+    }
+
+    fn description(&self) -> String {
+        let device = match self.device {
+            Some(device) => device,
+            None => return "Press A on the controller or keyboard you want to set up.".to_owned(),
+        };
+        if let Some(row) = self.waiting {
+            return match (row, device) {
+                (Row::Button(button), tputil::InputMethod::Keyboard(_)) => format!(
+                    "Press the new key for {} (Escape cancels).",
+                    button_label(button)
+                ),
+                (Row::Button(button), _) => {
+                    format!("Press the new button for {}.", button_label(button))
+                }
+                (Row::Axis(axis), _) => {
+                    format!("Move the stick or pad for {} movement.", axis_label(axis))
+                }
+                _ => String::new(),
+            };
+        }
+        match self.rows().get(self.menu.selected) {
+            Some(Row::Invert(_)) => "Flip the direction of this axis.",
+            Some(Row::Deadzone) => "Ignore stick movements smaller than this.",
+            Some(Row::Reset) => "Go back to the default controls for this device.",
+            Some(Row::Back) => "",
+            _ => "Press A to choose a new binding.",
+        }
+        .to_owned()
+    }
+
+    fn choose_device(&mut self, input: &tputil::InputState) -> game::UpdateResult {
+        let south = input.get_pressed_any(tputil::Button::South);
+        let east = input.get_pressed_any(tputil::Button::East);
+        if south.is_empty() && east.is_empty() {
+            self.released = true;
+        } else if self.released {
+            let device = south.into_iter().find(|method| match method {
+                tputil::InputMethod::Keyboard(_) | tputil::InputMethod::Gamepad(_) => true,
+                tputil::InputMethod::Virtual(_) => false,
+            });
+            match device {
+                Some(device) => {
+                    if let tputil::InputMethod::Gamepad(id) = device {
+                        self.gamepad_name = input.gamepad_name(id).unwrap_or_default();
+                    }
+                    self.device = Some(device);
+                }
+                None => return game::UpdateResult::Pop,
+            }
+        }
+        game::UpdateResult::Continue
+    }
+
+    // returns whether a binding was chosen
+    fn wait_for_binding(&mut self, row: Row, input: &tputil::InputState) -> bool {
+        match self.device {
+            Some(tputil::InputMethod::Keyboard(slot)) => {
+                let keys = input.pressed_keys();
+                self.held_keys.retain(|key| keys.contains(key));
+                let key = match keys.into_iter().find(|key| !self.held_keys.contains(key)) {
+                    Some(key) => key,
+                    None => return false,
+                };
+                if let Row::Button(button) = row {
+                    if key != Key::Escape {
+                        self.bindings.set_key(slot, button, key);
+                    }
+                }
+                true
+            }
+            Some(tputil::InputMethod::Gamepad(id)) => {
+                let button = input.raw_gamepad_button(id);
+                let axis = input.raw_gamepad_axis(id);
+                if button.is_none() && axis.is_none() {
+                    self.released = true;
+                    return false;
+                }
+                if !self.released {
+                    return false;
+                }
+                let gamepad = self.bindings.gamepad_mut(&self.gamepad_name);
+                match (row, button, axis) {
+                    (Row::Button(wanted), Some(physical), _) => {
+                        gamepad.set_button(wanted, physical)
+                    }
+                    (Row::Axis(tputil::Axis::X), _, Some(physical)) => {
+                        gamepad.x_axes = vec![physical]
+                    }
+                    (Row::Axis(tputil::Axis::Y), _, Some(physical)) => {
+                        gamepad.y_axes = vec![physical]
+                    }
+                    _ => return false,
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    // returns whether anything changed
+    fn change(&mut self, row: Row, direction: i8) -> bool {
+        match row {
+            Row::Invert(axis) => {
+                let gamepad = self.bindings.gamepad_mut(&self.gamepad_name);
+                match axis {
+                    tputil::Axis::X => gamepad.invert_x = !gamepad.invert_x,
+                    tputil::Axis::Y => gamepad.invert_y = !gamepad.invert_y,
+                }
+            }
+            Row::Deadzone => {
+                let gamepad = self.bindings.gamepad_mut(&self.gamepad_name);
+                gamepad.deadzone = (gamepad.deadzone + f32::from(direction) * 0.05).clamp(0.0, 0.5);
+            }
+            Row::Reset => match self.device {
+                Some(tputil::InputMethod::Keyboard(slot)) => {
+                    let mut defaults = bindings::KeyLayout::defaults();
+                    if slot < defaults.len() && slot < self.bindings.keyboards.len() {
+                        self.bindings.keyboards[slot] = defaults.swap_remove(slot);
+                    }
+                }
+                _ => {
+                    self.bindings.gamepads.remove(&self.gamepad_name);
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl game::State for ControlsState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        states::menu::dim(renderer, trans);
+        let title = match self.device {
+            Some(tputil::InputMethod::Keyboard(slot)) => format!("Keyboard {}", slot + 1),
+            Some(tputil::InputMethod::Gamepad(_)) => self.gamepad_name.clone(),
+            _ => "Controls".to_owned(),
+        };
+        let choices: Vec<String> = match self.device {
+            Some(_) => self
+                .rows()
+                .into_iter()
+                .map(|row| self.choice(row))
+                .collect(),
+            None => Vec::new(),
+        };
+        states::menu::render_choices(
+            &title,
+            &choices,
+            self.menu.selected,
+            &self.description(),
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let device = match self.device {
+            Some(device) => device,
+            None => return self.choose_device(props.input),
+        };
+
+        let changed = if let Some(row) = self.waiting {
+            if !self.wait_for_binding(row, props.input) {
+                return game::UpdateResult::Continue;
+            }
+            self.waiting = None;
+            // ignore the key that was just bound
+            let selected = self.menu.selected;
+            self.menu = states::menu::Menu::new();
+            self.menu.selected = selected;
+            true
+        } else {
+            let rows = self.rows();
+            match self.menu.update(props.input, &[device], rows.len()) {
+                Some(states::menu::MenuAction::Select(index)) => match rows[index] {
+                    Row::Button(_) | Row::Axis(_) => {
+                        self.waiting = Some(rows[index]);
+                        self.released = false;
+                        self.held_keys = props.input.pressed_keys();
+                        false
+                    }
+                    Row::Back => return game::UpdateResult::Pop,
+                    row => self.change(row, 1),
+                },
+                Some(states::menu::MenuAction::Adjust(index, direction)) => match rows[index] {
+                    row @ Row::Invert(_) | row @ Row::Deadzone => self.change(row, direction),
+                    _ => false,
+                },
+                Some(states::menu::MenuAction::Back) => return game::UpdateResult::Pop,
+                None => false,
+            }
+        };
+        if changed {
+            props.input.bindings = self.bindings.clone();
+            props.input.bindings.save();
+        }
+
+        game::UpdateResult::Continue
+    }
+}
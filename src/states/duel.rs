@@ -0,0 +1,131 @@
+use crate::cpu;
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use crate::states::ingame::GameInfo;
+
+pub struct DuelSetupState {
+    game: GameInfo,
+    turn: usize,
+    opponents: Vec<usize>,
+    menu: states::menu::Menu,
+    wager: u16,
+    thinking: f64,
+}
+
+impl DuelSetupState {
+    const WAGER_STEP: u16 = 5;
+    const DEFAULT_WAGER: u16 = 10;
+
+    pub fn new(game: GameInfo, turn: usize) -> Self {
+        let opponents = (0..game.players.len()).filter(|&i| i != turn).collect();
+        let wager = DuelSetupState::DEFAULT_WAGER.min(game.players[turn].coins);
+        DuelSetupState {
+            game,
+            turn,
+            opponents,
+            menu: states::menu::Menu::new(),
+            wager,
+            thinking: 0.0,
+        }
+    }
+}
+
+impl game::State for DuelSetupState {
+    fn pausable(&self) -> bool {
+        true
+    }
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        let transform = self.game.render(
+            renderer,
+            trans,
+            states::ingame::BOARD_CENTER,
+            0.06,
+            utils,
+            &[self.turn],
+        );
+        let player = &self.game.players[self.turn];
+        let space = self.game.map.get_space(player.space).unwrap();
+        renderer.rectangle(
+            tputil::COLORS[player.player.color],
+            graphics::rectangle::centered_square(space.pos.x, space.pos.y, 0.7),
+            transform,
+        );
+
+        let choices: Vec<String> = self
+            .opponents
+            .iter()
+            .map(|&i| {
+                let opponent = &self.game.players[i];
+                format!(
+                    "{} ({} coins)",
+                    tputil::COLOR_NAMES[opponent.player.color],
+                    opponent.coins
+                )
+            })
+            .collect();
+        states::menu::render_choices(
+            "Duel! Pick an opponent",
+            &choices,
+            self.menu.selected,
+            &format!("Wager: {} coins (left/right to change)", self.wager),
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let input = self.game.players[self.turn].player.input;
+        let count = self.opponents.len();
+        let action = match self.game.players[self.turn].player.cpu {
+            Some(difficulty) => {
+                self.thinking += props.time;
+                if self.thinking > cpu::THINK_TIME {
+                    let (opponent, wager) = cpu::choose_duel(
+                        &self.game,
+                        self.turn,
+                        &self.opponents,
+                        difficulty,
+                        props.rng,
+                    );
+                    self.wager = wager;
+                    Some(states::menu::MenuAction::Select(opponent))
+                } else {
+                    None
+                }
+            }
+            None => self.menu.update(props.input, &[input], count),
+        };
+        match action {
+            Some(states::menu::MenuAction::Adjust(_, direction)) => {
+                let coins = self.game.players[self.turn].coins;
+                self.wager = if direction > 0 {
+                    (self.wager + DuelSetupState::WAGER_STEP).min(coins)
+                } else {
+                    self.wager.saturating_sub(DuelSetupState::WAGER_STEP)
+                };
+            }
+            Some(states::menu::MenuAction::Select(selected)) => {
+                return game::UpdateResult::NewState(Box::new(
+                    states::minigame::MinigameDescriptionState::new_duel(
+                        self.game.clone(),
+                        self.turn,
+                        self.opponents[selected],
+                        self.wager,
+                        props.rng,
+                    ),
+                ));
+            }
+            _ => {}
+        }
+
+        game::UpdateResult::Continue
+    }
+}
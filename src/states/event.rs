@@ -0,0 +1,127 @@
+use crate::events;
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use crate::states::ingame::GameInfo;
+use graphics::Transformed;
+use rand::Rng;
+
+pub struct EventState {
+    game: GameInfo,
+    turn: usize,
+    event: &'static events::Event,
+    target: Option<usize>,
+    time: f64,
+    applied: bool,
+}
+
+impl EventState {
+    const APPEAR_TIME: f64 = 0.3;
+    const APPLY_TIME: f64 = 2.0;
+    const END_TIME: f64 = 3.5;
+
+    pub fn new(game: GameInfo, turn: usize, rng: &mut game::GameRng) -> Self {
+        let event = events::choose_event(game.players.len(), rng);
+        let target = if event.effect.needs_target() {
+            let others: Vec<usize> = (0..game.players.len()).filter(|&i| i != turn).collect();
+            Some(others[rng.gen_range(0, others.len())])
+        } else {
+            None
+        };
+        EventState {
+            game,
+            turn,
+            event,
+            target,
+            time: 0.0,
+            applied: false,
+        }
+    }
+}
+
+impl game::State for EventState {
+    fn pausable(&self) -> bool {
+        true
+    }
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        const COLOR1: [f32; 4] = [0.6, 0.3, 0.9, 1.0];
+        const COLOR2: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+        let transform = self.game.render(
+            renderer,
+            trans,
+            states::ingame::BOARD_CENTER,
+            0.06,
+            utils,
+            &[self.turn],
+        );
+        let player = &self.game.players[self.turn];
+        let space = self.game.map.get_space(player.space).unwrap();
+        renderer.rectangle(
+            tputil::COLORS[player.player.color],
+            graphics::rectangle::centered_square(space.pos.x, space.pos.y, 0.7),
+            transform,
+        );
+
+        let size = (self.time / EventState::APPEAR_TIME).min(1.0);
+        let card = trans.scale(size, size);
+        renderer.rectangle(COLOR1, [-0.62, -0.32, 1.24, 0.64], card);
+        renderer.rectangle(COLOR2, [-0.6, -0.3, 1.2, 0.6], card);
+        if size < 1.0 {
+            return;
+        }
+
+        utils.draw_text_align(
+            self.event.name,
+            0.1,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -0.28),
+            renderer,
+        );
+        utils.draw_text_align_wrap(
+            self.event.description,
+            0.05,
+            tputil::Alignment::MIDDLE_CENTER,
+            1.1,
+            trans,
+            renderer,
+        );
+        if let Some(target) = self.target {
+            let color = self.game.players[target].player.color;
+            renderer.rectangle(
+                tputil::COLORS[color],
+                graphics::rectangle::centered_square(-0.2, 0.18, 0.05),
+                trans,
+            );
+            utils.draw_text_align(
+                tputil::COLOR_NAMES[color],
+                0.06,
+                tputil::Alignment::MIDDLE_LEFT,
+                trans.trans(-0.1, 0.18),
+                renderer,
+            );
+        }
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        self.time += props.time;
+        if !self.applied && self.time > EventState::APPLY_TIME {
+            self.applied = true;
+            self.game
+                .apply_event(self.turn, self.event.effect, self.target, props.rng);
+        }
+        if self.time > EventState::END_TIME {
+            return crate::to_new_state!(|prev: Self| {
+                Box::new(states::ingame::SpaceResultState::new(prev.game, prev.turn))
+            });
+        }
+
+        game::UpdateResult::Continue
+    }
+}
@@ -0,0 +1,94 @@
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use crate::states::ingame::GameInfo;
+use graphics::Transformed;
+
+// pick minigames to play without a board, coins only keep score
+pub struct FreePlayState {
+    game: GameInfo,
+    minigames: Vec<(usize, &'static str)>,
+    menu: states::menu::Menu,
+}
+
+impl FreePlayState {
+    pub fn new(game: GameInfo) -> Self {
+        FreePlayState {
+            game,
+            minigames: states::minigame::free_play_minigames(),
+            menu: states::menu::Menu::new(),
+        }
+    }
+}
+
+impl game::State for FreePlayState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        let count = self.game.players.len();
+        let spacing = 2.0 / (count + 1) as f64;
+        for (i, player) in self.game.players.iter().enumerate() {
+            let x = spacing * (i as f64 + 1.0) - 1.0;
+            renderer.rectangle(
+                tputil::COLORS[player.player.color],
+                graphics::rectangle::centered_square(x, -0.85, 0.05),
+                trans,
+            );
+            utils.draw_text_align(
+                &player.coins.to_string(),
+                0.06,
+                tputil::Alignment::TOP_CENTER,
+                trans.trans(x, -0.75),
+                renderer,
+            );
+        }
+
+        let mut choices: Vec<String> = self
+            .minigames
+            .iter()
+            .map(|&(_, title)| title.to_owned())
+            .collect();
+        choices.push("Back to menu".to_owned());
+        states::menu::render_choices(
+            "Minigame free play",
+            &choices,
+            self.menu.selected,
+            "Coins won are only kept until you leave.",
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let controllers: Vec<_> = self
+            .game
+            .players
+            .iter()
+            .map(|player| player.player.input)
+            .collect();
+        let count = self.minigames.len() + 1;
+        match self.menu.update(props.input, &controllers, count) {
+            Some(states::menu::MenuAction::Select(selected)) => {
+                if let Some(&(minigame, _)) = self.minigames.get(selected) {
+                    return game::UpdateResult::NewState(Box::new(
+                        states::minigame::MinigameDescriptionState::new_free_play(
+                            self.game.clone(),
+                            minigame,
+                            props.rng,
+                        ),
+                    ));
+                }
+                game::UpdateResult::NewState(Box::new(states::setup::MenuState::new()))
+            }
+            Some(states::menu::MenuAction::Back) => {
+                game::UpdateResult::NewState(Box::new(states::setup::MenuState::new()))
+            }
+            _ => game::UpdateResult::Continue,
+        }
+    }
+}
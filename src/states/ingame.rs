@@ -1,5 +1,9 @@
 use crate::board;
+use crate::cpu;
+use crate::events;
 use crate::game;
+use crate::items;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
@@ -7,97 +11,187 @@ use graphics::Transformed;
 use rand::Rng;
 use std::f64::consts::PI;
 
-#[derive(Clone)]
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub minigame_coins: u16,
+    pub spaces_moved: u16,
+    pub negative_spaces: u16,
+    // coins won or lost anywhere, not counting what was spent at shops and stars
+    pub coins_gained: u16,
+    pub coins_lost: u16,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlayerInfo {
     pub player: tputil::Player,
     pub space: board::SpaceID,
     pub coins: u16,
     pub stars: u8,
+    pub items: Vec<items::Item>,
+    pub stats: PlayerStats,
+    pub landed: Option<board::SpaceType>,
+}
+
+impl PlayerInfo {
+    pub fn add_coins(&mut self, amount: i32) {
+        let coins = (i32::from(self.coins) + amount).max(0);
+        let change = coins - i32::from(self.coins);
+        if change > 0 {
+            self.stats.coins_gained = self.stats.coins_gained.saturating_add(change as u16);
+        } else {
+            self.stats.coins_lost = self.stats.coins_lost.saturating_add((-change) as u16);
+        }
+        self.coins = coins as u16;
+    }
 }
 
 impl From<tputil::Player> for PlayerInfo {
     fn from(player: tputil::Player) -> Self {
         PlayerInfo {
             player,
-            space: 0,
+            space: board::START_SPACE,
             coins: 0,
             stars: 0,
+            items: Vec::new(),
+            stats: Default::default(),
+            landed: None,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct GameInfo {
     pub players: Vec<PlayerInfo>,
+    #[serde(with = "board::source")]
     pub map: board::Board,
     pub star_space: board::SpaceID,
+    pub turn: u16,
+    pub max_turns: u16,
 }
 
-const BOARD_CENTER: tputil::Point2D = tputil::Point2D { x: 0.5, y: 0.5 };
+pub const BOARD_CENTER: tputil::Point2D = tputil::Point2D { x: 0.5, y: 0.5 };
 
 impl GameInfo {
-    pub fn new<I>(players: I, map: board::Board) -> Self
+    pub fn new<I>(players: I, map: board::Board, max_turns: u16, rng: &mut game::GameRng) -> Self
     where
         I: IntoIterator<Item = PlayerInfo>,
     {
-        let star_space = GameInfo::choose_star_space(&map);
+        let star_space = GameInfo::choose_star_space(&map, rng);
         GameInfo {
             players: players.into_iter().collect(),
             map,
             star_space,
+            turn: 1,
+            max_turns,
         }
     }
 
-    fn choose_star_space(map: &board::Board) -> board::SpaceID {
-        map.spaces[rand::thread_rng().gen_range(0, map.spaces.len())].id
+    pub fn is_last_turn(&self) -> bool {
+        self.turn >= self.max_turns
     }
 
-    fn render(
+    // player indices, best first
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.players.len()).collect();
+        order.sort_by(|&a, &b| {
+            let a = &self.players[a];
+            let b = &self.players[b];
+            (b.stars, b.coins).cmp(&(a.stars, a.coins))
+        });
+        order
+    }
+
+    fn choose_star_space(map: &board::Board, rng: &mut game::GameRng) -> board::SpaceID {
+        map.spaces[rng.gen_range(0, map.spaces.len())].id
+    }
+
+    pub const STAR_PRICE: u16 = 20;
+
+    pub fn buy_star(&mut self, player: usize, rng: &mut game::GameRng) {
+        if self.players[player].coins >= GameInfo::STAR_PRICE {
+            self.players[player].coins -= GameInfo::STAR_PRICE;
+            self.players[player].stars += 1;
+            self.star_space = GameInfo::choose_star_space(&self.map, rng);
+        }
+    }
+
+    pub fn steal_coins(&mut self, thief: usize, amount: u16) {
+        let victim = (0..self.players.len())
+            .filter(|&i| i != thief)
+            .max_by_key(|&i| self.players[i].coins);
+        if let Some(victim) = victim {
+            let amount = i32::from(amount.min(self.players[victim].coins));
+            self.players[victim].add_coins(-amount);
+            self.players[thief].add_coins(amount);
+        }
+    }
+
+    pub fn apply_event(
+        &mut self,
+        player: usize,
+        effect: events::Effect,
+        target: Option<usize>,
+        rng: &mut game::GameRng,
+    ) {
+        match effect {
+            events::Effect::SwapCoins => {
+                if let Some(target) = target {
+                    let difference = i32::from(self.players[target].coins)
+                        - i32::from(self.players[player].coins);
+                    self.players[player].add_coins(difference);
+                    self.players[target].add_coins(-difference);
+                }
+            }
+            events::Effect::SwapPositions => {
+                if let Some(target) = target {
+                    let space = self.players[player].space;
+                    self.players[player].space = self.players[target].space;
+                    self.players[target].space = space;
+                }
+            }
+            events::Effect::EveryoneLoses(amount) => {
+                for info in &mut self.players {
+                    info.add_coins(-i32::from(amount));
+                }
+            }
+            events::Effect::EveryoneGains(amount) => {
+                for info in &mut self.players {
+                    info.add_coins(i32::from(amount));
+                }
+            }
+            events::Effect::GiveToEveryone(amount) => {
+                for other in 0..self.players.len() {
+                    if other != player {
+                        let given = i32::from(amount.min(self.players[player].coins));
+                        self.players[player].add_coins(-given);
+                        self.players[other].add_coins(given);
+                    }
+                }
+            }
+            events::Effect::MoveStar => {
+                self.star_space = GameInfo::choose_star_space(&self.map, rng);
+            }
+        }
+    }
+
+    pub fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         center: tputil::Point2D,
         scale: f64,
         utils: &mut game::Utils,
         hide: &[usize],
     ) -> graphics::math::Matrix2d {
-        const COLOR1: [f32; 4] = [1.0, 0.2, 0.0, 1.0];
-        const COLOR2: [f32; 4] = [0.0, 0.8, 1.0, 1.0];
-        const COLOR3: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
-        const COLOR4: [f32; 4] = [0.8, 0.7, 0.6, 1.0];
-
         let transform = (-center).translate(trans).scale(scale, scale);
-        for start in &self.map.spaces {
-            for transition in start.transitions.iter() {
-                let end = self.map.get_space(transition.to).unwrap();
-                graphics::line(
-                    COLOR4,
-                    0.2,
-                    [start.pos.x, start.pos.y, end.pos.x, end.pos.y],
-                    transform,
-                    gl,
-                );
-            }
-        }
+        render_board(&self.map, Some(self.star_space), transform, renderer);
         for space in &self.map.spaces {
-            graphics::rectangle(
-                if space.id == self.star_space {
-                    COLOR3
-                } else {
-                    match space.space_type {
-                        board::SpaceType::Positive => COLOR2,
-                        board::SpaceType::Negative => COLOR1,
-                    }
-                },
-                graphics::rectangle::centered_square(space.pos.x, space.pos.y, 1.0),
-                transform,
-                gl,
-            );
             let mut so_far = 0;
             for i in 0..self.players.len() {
                 if self.players[i].space == space.id && !hide.contains(&i) {
                     let color = tputil::COLORS[self.players[i].player.color];
-                    graphics::rectangle(
+                    renderer.rectangle(
                         color,
                         [
                             space.pos.x - 1.0 + f64::from(so_far) * 2.0 / 3.0,
@@ -106,12 +200,18 @@ impl GameInfo {
                             0.5,
                         ],
                         transform,
-                        gl,
                     );
                     so_far += 1;
                 }
             }
         }
+        utils.draw_text_align(
+            &format!("Turn {}/{}", self.turn, self.max_turns),
+            0.08,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -1.0),
+            renderer,
+        );
         for i in 0..self.players.len().min(4) {
             let coins = format!("{}", self.players[i].coins);
             let stars = format!("{}", self.players[i].stars);
@@ -139,31 +239,75 @@ impl GameInfo {
 
             let color = tputil::COLORS[self.players[i].player.color];
 
-            graphics::rectangle(
+            renderer.rectangle(
                 color,
                 [x + size / 3.0, y + size / 3.0, size / 3.0, size / 3.0],
                 trans,
-                gl,
             );
             utils.draw_text_align(
                 &coins,
                 text_size,
                 text_align,
                 trans.trans(x + coin_text_x, y + size / 3.0),
-                gl,
+                renderer,
             );
             utils.draw_text_align(
                 &stars,
                 text_size,
                 text_align,
                 trans.trans(x + star_text_x, y + size / 1.5),
-                gl,
+                renderer,
             );
         }
         transform
     }
 }
 
+pub fn render_board(
+    map: &board::Board,
+    star_space: Option<board::SpaceID>,
+    transform: graphics::math::Matrix2d,
+    renderer: &mut dyn render::Renderer,
+) {
+    const COLOR1: [f32; 4] = [1.0, 0.2, 0.0, 1.0];
+    const COLOR2: [f32; 4] = [0.0, 0.8, 1.0, 1.0];
+    const COLOR3: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
+    const COLOR4: [f32; 4] = [0.8, 0.7, 0.6, 1.0];
+    const COLOR5: [f32; 4] = [0.2, 0.7, 0.2, 1.0];
+    const COLOR6: [f32; 4] = [0.6, 0.3, 0.9, 1.0];
+    const COLOR7: [f32; 4] = [0.3, 0.3, 0.3, 1.0];
+
+    for start in &map.spaces {
+        for transition in start.transitions.iter() {
+            if let Some(end) = map.get_space(transition.to) {
+                renderer.line(
+                    COLOR4,
+                    0.2,
+                    [start.pos.x, start.pos.y, end.pos.x, end.pos.y],
+                    transform,
+                );
+            }
+        }
+    }
+    for space in &map.spaces {
+        renderer.rectangle(
+            if Some(space.id) == star_space {
+                COLOR3
+            } else {
+                match space.space_type {
+                    board::SpaceType::Positive => COLOR2,
+                    board::SpaceType::Negative => COLOR1,
+                    board::SpaceType::Shop => COLOR5,
+                    board::SpaceType::Event => COLOR6,
+                    board::SpaceType::Duel => COLOR7,
+                }
+            },
+            graphics::rectangle::centered_square(space.pos.x, space.pos.y, 1.0),
+            transform,
+        );
+    }
+}
+
 pub struct BoardMoveState {
     game: GameInfo,
     time: f64,
@@ -195,15 +339,18 @@ impl BoardMoveState {
 }
 
 impl game::State for BoardMoveState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         let transform = self
             .game
-            .render(gl, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
+            .render(renderer, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
         let start = self
             .game
             .map
@@ -214,18 +361,17 @@ impl game::State for BoardMoveState {
 
         let pos = tputil::Point2D::lerp(start.pos, end.pos, self.time / self.duration);
         let color = tputil::COLORS[self.game.players[self.turn].player.color];
-        graphics::rectangle(
+        renderer.rectangle(
             color,
             graphics::rectangle::centered_square(pos.x, pos.y, 0.7),
             transform,
-            gl,
         );
         utils.draw_text_align(
             &self.remaining.to_string(),
             1.0,
             tputil::Alignment::BOTTOM_CENTER,
             transform.trans(pos.x, pos.y - 1.0),
-            gl,
+            renderer,
         );
     }
     fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
@@ -240,13 +386,10 @@ impl game::State for BoardMoveState {
 
             let mut new_game_state = self.game.clone();
             new_game_state.players[self.turn].space = transition.to;
+            new_game_state.players[self.turn].stats.spaces_moved += 1;
 
             if transition.to == self.game.star_space {
-                if self.game.players[self.turn].coins >= 20 {
-                    new_game_state.players[self.turn].coins -= 20;
-                    new_game_state.players[self.turn].stars += 1;
-                    new_game_state.star_space = GameInfo::choose_star_space(&self.game.map);
-                }
+                new_game_state.buy_star(self.turn, props.rng);
             }
 
             if self.remaining > 1 {
@@ -266,21 +409,35 @@ impl game::State for BoardMoveState {
                     )));
                 }
             } else {
-                new_game_state.players[self.turn].coins =
-                    (i32::from(new_game_state.players[self.turn].coins)
-                        + i32::from(
-                            match self
-                                .game
-                                .map
-                                .get_space(new_game_state.players[self.turn].space)
-                                .unwrap()
-                                .space_type
-                            {
-                                board::SpaceType::Positive => 3,
-                                board::SpaceType::Negative => -(3 as i8),
-                            },
-                        ))
-                    .max(0) as u16;
+                let space_type = self.game.map.get_space(transition.to).unwrap().space_type;
+                new_game_state.players[self.turn].landed = Some(space_type);
+                match space_type {
+                    board::SpaceType::Shop => {
+                        return game::UpdateResult::NewState(Box::new(
+                            states::shop::ShopState::new(new_game_state, self.turn),
+                        ));
+                    }
+                    board::SpaceType::Event => {
+                        return game::UpdateResult::NewState(Box::new(
+                            states::event::EventState::new(new_game_state, self.turn, props.rng),
+                        ));
+                    }
+                    board::SpaceType::Duel if new_game_state.players.len() > 1 => {
+                        return game::UpdateResult::NewState(Box::new(
+                            states::duel::DuelSetupState::new(new_game_state, self.turn),
+                        ));
+                    }
+                    _ => {}
+                }
+                if let board::SpaceType::Negative = space_type {
+                    new_game_state.players[self.turn].stats.negative_spaces += 1;
+                }
+                let amount = match space_type {
+                    board::SpaceType::Positive => 3,
+                    board::SpaceType::Negative => -3,
+                    board::SpaceType::Shop | board::SpaceType::Event | board::SpaceType::Duel => 0,
+                };
+                new_game_state.players[self.turn].add_coins(amount);
                 return game::UpdateResult::NewState(Box::new(SpaceResultState {
                     game: new_game_state,
                     time: 0.0,
@@ -293,30 +450,42 @@ impl game::State for BoardMoveState {
     }
 }
 
-struct SpaceResultState {
+pub struct SpaceResultState {
     game: GameInfo,
     time: f64,
     turn: usize,
 }
 
+impl SpaceResultState {
+    pub fn new(game: GameInfo, turn: usize) -> Self {
+        SpaceResultState {
+            game,
+            time: 0.0,
+            turn,
+        }
+    }
+}
+
 impl game::State for SpaceResultState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         let transform = self
             .game
-            .render(gl, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
+            .render(renderer, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
         let player = &self.game.players[self.turn];
         let color = tputil::COLORS[player.player.color];
         let space = self.game.map.get_space(player.space).unwrap();
-        graphics::rectangle(
+        renderer.rectangle(
             color,
             graphics::rectangle::centered_square(space.pos.x, space.pos.y, 0.7),
             transform,
-            gl,
         );
     }
     fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
@@ -327,11 +496,12 @@ impl game::State for SpaceResultState {
                     Box::new(DieRollState::new(prev.game, prev.turn + 1))
                 });
             } else {
-                return crate::to_new_state!(|prev: Self| {
-                    Box::new(states::minigame::MinigameDescriptionState::new_random(
-                        prev.game,
-                    ))
-                });
+                return game::UpdateResult::NewState(Box::new(
+                    states::minigame::MinigameDescriptionState::new_random(
+                        self.game.clone(),
+                        props.rng,
+                    ),
+                ));
             }
         }
 
@@ -344,23 +514,117 @@ pub struct DieRollState {
     time: f64,
     jump: bool,
     turn: usize,
-    number: u8,
+    // per die, so a second die doesn't just follow the first
+    cycles: Vec<f64>,
+    numbers: Vec<u8>,
+    max_number: u8,
+    item_menu: Option<states::menu::Menu>,
+    released: bool,
+    // time a CPU player has spent on the current decision, and will spend
+    thinking: f64,
+    think_time: Option<f64>,
 }
 
 impl DieRollState {
+    pub(crate) const MAX_NUMBER: u8 = 9;
+    const SUPER_MAX_NUMBER: u8 = 15;
+    const CYCLE_RATE: f64 = 60.0;
+    const SECOND_CYCLE_RATE: f64 = 47.0;
+
     pub fn new(game: GameInfo, turn: usize) -> Self {
+        let item_menu = if game.players[turn].items.is_empty() {
+            None
+        } else {
+            Some(states::menu::Menu::new())
+        };
         DieRollState {
             game,
             turn,
-            number: 0,
+            cycles: vec![0.0],
+            numbers: vec![0],
+            max_number: DieRollState::MAX_NUMBER,
             jump: false,
             time: 0.0,
+            released: item_menu.is_none(),
+            item_menu,
+            thinking: 0.0,
+            think_time: None,
         }
     }
+
+    fn total(&self) -> u8 {
+        self.numbers.iter().sum()
+    }
+
+    fn use_item(&mut self, index: usize, rng: &mut game::GameRng) -> Option<game::UpdateResult> {
+        match self.game.players[self.turn].items.remove(index) {
+            items::Item::DoubleDice => {
+                self.numbers = vec![0, rng.gen_range(1, DieRollState::MAX_NUMBER + 1)];
+                self.cycles = vec![0.0, rng.gen::<f64>()];
+            }
+            items::Item::SuperDice => self.max_number = DieRollState::SUPER_MAX_NUMBER,
+            items::Item::StealCoins => self.game.steal_coins(self.turn, items::Item::STEAL_AMOUNT),
+            items::Item::WarpToStar => {
+                self.game.players[self.turn].space = self.game.star_space;
+                self.game.players[self.turn].landed = self
+                    .game
+                    .map
+                    .get_space(self.game.star_space)
+                    .map(|space| space.space_type);
+                self.game.buy_star(self.turn, rng);
+                return Some(crate::to_new_state!(|prev: Self| {
+                    Box::new(SpaceResultState::new(prev.game, prev.turn))
+                }));
+            }
+        }
+        None
+    }
 }
 
 impl game::State for DieRollState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let input = self.game.players[self.turn].player.input;
+        let cpu = self.game.players[self.turn].player.cpu;
+        let pressed = match cpu {
+            Some(difficulty) => {
+                let think_time = *self
+                    .think_time
+                    .get_or_insert_with(|| cpu::think_time(difficulty, props.rng));
+                self.thinking += props.time;
+                self.thinking > think_time
+            }
+            None => props.input.is_pressed(&input, tputil::Button::South),
+        };
+
+        if let Some(ref mut menu) = self.item_menu {
+            let count = self.game.players[self.turn].items.len() + 1;
+            let action = match cpu {
+                Some(difficulty) if pressed => {
+                    self.thinking = 0.0;
+                    self.think_time = None;
+                    let choice = cpu::choose_item(&self.game, self.turn, difficulty, props.rng);
+                    Some(states::menu::MenuAction::Select(
+                        choice.unwrap_or(count - 1),
+                    ))
+                }
+                Some(_) => None,
+                None => menu.update(props.input, &[input], count),
+            };
+            if let Some(states::menu::MenuAction::Select(selected)) = action {
+                self.item_menu = None;
+                if selected + 1 < count {
+                    if let Some(result) = self.use_item(selected, props.rng) {
+                        return result;
+                    }
+                }
+            }
+
+            return game::UpdateResult::Continue;
+        }
+
         if self.jump {
             self.time += props.time * 4.0;
             if self.time > 2.0 {
@@ -371,28 +635,33 @@ impl game::State for DieRollState {
                     .unwrap();
                 if space.transitions.len() > 1 {
                     return crate::to_new_state!(|prev: Self| {
-                        Box::new(TransitionChoiceState::new(
-                            prev.game,
-                            prev.turn,
-                            prev.number,
-                        ))
+                        let total = prev.total();
+                        Box::new(TransitionChoiceState::new(prev.game, prev.turn, total))
                     });
                 } else {
                     return crate::to_new_state!(|prev: Self| {
-                        Box::new(BoardMoveState::new(prev.game, 0, prev.turn, prev.number))
+                        let total = prev.total();
+                        Box::new(BoardMoveState::new(prev.game, 0, prev.turn, total))
                     });
                 }
             }
-        } else if props.input.is_pressed(
-            &self.game.players[self.turn].player.input,
-            tputil::Button::South,
-        ) {
+        } else if !pressed {
+            self.released = true;
+        } else if self.released {
             self.jump = true;
         }
         if self.time < 1.0 {
-            self.number += 1;
-            if self.number > 9 {
-                self.number = 1;
+            for (i, (number, cycle)) in self.numbers.iter_mut().zip(&mut self.cycles).enumerate() {
+                let rate = if i == 0 {
+                    DieRollState::CYCLE_RATE
+                } else {
+                    DieRollState::SECOND_CYCLE_RATE
+                };
+                *cycle += props.time * rate;
+                while *cycle >= 1.0 {
+                    *cycle -= 1.0;
+                    *number = *number % self.max_number + 1;
+                }
             }
         }
 
@@ -400,13 +669,13 @@ impl game::State for DieRollState {
     }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         let transform = self
             .game
-            .render(gl, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
+            .render(renderer, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
         let player = &self.game.players[self.turn];
         let color = tputil::COLORS[player.player.color];
         let space = self.game.map.get_space(player.space).unwrap();
@@ -415,23 +684,49 @@ impl game::State for DieRollState {
         } else {
             0.0
         };
-        graphics::rectangle(
+        renderer.rectangle(
             color,
             graphics::rectangle::centered_square(space.pos.x, space.pos.y - y, 0.7),
             transform,
-            gl,
         );
+
+        if let Some(ref menu) = self.item_menu {
+            let selected = menu.selected;
+            let mut choices: Vec<String> = player
+                .items
+                .iter()
+                .map(|item| item.name().to_owned())
+                .collect();
+            choices.push("Roll".to_owned());
+            let description = player
+                .items
+                .get(selected)
+                .map(|item| item.description())
+                .unwrap_or("Roll without using an item.");
+            states::menu::render_choices(
+                "Use an item?",
+                &choices,
+                selected,
+                description,
+                trans,
+                renderer,
+                utils,
+            );
+            return;
+        }
+
         let off = if self.jump && self.time > 1.0 {
             1.0 + y
         } else {
             2.0
         };
+        let numbers: Vec<String> = self.numbers.iter().map(u8::to_string).collect();
         utils.draw_text_align(
-            &self.number.to_string(),
+            &numbers.join(" "),
             1.0,
             tputil::Alignment::BOTTOM_CENTER,
             transform.trans(space.pos.x, space.pos.y - off),
-            gl,
+            renderer,
         );
     }
 }
@@ -442,6 +737,7 @@ struct TransitionChoiceState {
     time: f64,
     selected: usize,
     remaining: u8,
+    chosen: bool,
 }
 
 impl TransitionChoiceState {
@@ -452,18 +748,34 @@ impl TransitionChoiceState {
             remaining,
             time: 0.0,
             selected: 0,
+            chosen: false,
         }
     }
 }
 
 impl game::State for TransitionChoiceState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
         self.time += props.time;
 
-        if props.input.is_pressed(
-            &self.game.players[self.turn].player.input,
-            tputil::Button::South,
-        ) {
+        let go = match self.game.players[self.turn].player.cpu {
+            Some(difficulty) => {
+                // show the choice for a moment before moving
+                if !self.chosen && self.time > cpu::THINK_TIME / 2.0 {
+                    self.chosen = true;
+                    self.selected =
+                        cpu::choose_transition(&self.game, self.turn, difficulty, props.rng);
+                }
+                self.time > cpu::THINK_TIME
+            }
+            None => props.input.is_pressed(
+                &self.game.players[self.turn].player.input,
+                tputil::Button::South,
+            ),
+        };
+        if go {
             crate::to_new_state!(|prev: Self| {
                 Box::new(BoardMoveState::new(
                     prev.game,
@@ -515,21 +827,20 @@ impl game::State for TransitionChoiceState {
     }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         let transform = self
             .game
-            .render(gl, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
+            .render(renderer, trans, BOARD_CENTER, 0.06, utils, &[self.turn]);
         let player = &self.game.players[self.turn];
         let color = tputil::COLORS[player.player.color];
         let space = self.game.map.get_space(player.space).unwrap();
-        graphics::rectangle(
+        renderer.rectangle(
             color,
             graphics::rectangle::centered_square(space.pos.x, space.pos.y, 0.7),
             transform,
-            gl,
         );
         for (index, transition) in space.transitions.iter().enumerate() {
             let dest_space = self.game.map.get_space(transition.to).unwrap();
@@ -544,15 +855,139 @@ impl game::State for TransitionChoiceState {
             } else {
                 COLOR3
             };
-            graphics::line(COLOR2, 0.2, [p1.x, p1.y, p2.x, p2.y], transform, gl);
-            graphics::line(color, 0.15, [p1.x, p1.y, p2.x, p2.y], transform, gl);
+            renderer.line(COLOR2, 0.2, [p1.x, p1.y, p2.x, p2.y], transform);
+            renderer.line(color, 0.15, [p1.x, p1.y, p2.x, p2.y], transform);
         }
         utils.draw_text_align(
             &self.remaining.to_string(),
             1.0,
             tputil::Alignment::BOTTOM_CENTER,
             transform.trans(space.pos.x, space.pos.y - 1.0),
-            gl,
+            renderer,
+        );
+    }
+}
+
+pub struct FinalResultsState {
+    game: GameInfo,
+    time: f64,
+}
+
+impl FinalResultsState {
+    pub fn new(game: GameInfo) -> Self {
+        FinalResultsState { game, time: 0.0 }
+    }
+
+    pub fn game(&self) -> &GameInfo {
+        &self.game
+    }
+}
+
+impl game::State for FinalResultsState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        let standings = self.game.standings();
+        let winner = &self.game.players[standings[0]];
+        utils.draw_text_align(
+            &format!("{} wins!", tputil::COLOR_NAMES[winner.player.color]),
+            0.15,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -1.0),
+            renderer,
         );
+
+        let scale = 1.4 / self.game.players.len() as f64;
+        let mut rank = 0;
+        let mut last_score = None;
+        for (i, &index) in standings.iter().enumerate() {
+            let player = &self.game.players[index];
+            let score = (player.stars, player.coins);
+            if last_score != Some(score) {
+                rank = i + 1;
+                last_score = Some(score);
+            }
+            let y = (i as f64 + 0.5) * scale - 0.6;
+            utils.draw_text_align(
+                &format!("{}.", rank),
+                scale / 2.0,
+                tputil::Alignment::MIDDLE_RIGHT,
+                trans.trans(-0.6, y),
+                renderer,
+            );
+            renderer.rectangle(
+                tputil::COLORS[player.player.color],
+                graphics::rectangle::centered_square(-0.4, y, scale / 4.0),
+                trans,
+            );
+            utils.draw_text_align(
+                &format!("{} stars, {} coins", player.stars, player.coins),
+                scale / 3.0,
+                tputil::Alignment::MIDDLE_LEFT,
+                trans.trans(-0.2, y),
+                renderer,
+            );
+        }
+
+        if self.time > 3.0 {
+            utils.draw_text_align(
+                "Press Start to return to the menu",
+                0.07,
+                tputil::Alignment::BOTTOM_CENTER,
+                trans.trans(0.0, 1.0),
+                renderer,
+            );
+        }
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        self.time += props.time;
+        if self.time > 3.0
+            && !props
+                .input
+                .get_pressed_any(tputil::Button::Start)
+                .is_empty()
+        {
+            return game::UpdateResult::NewState(Box::new(states::setup::MenuState::new()));
+        }
+
+        game::UpdateResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn game_info_matches_golden_image() {
+        let mut rng = game::GameRng::seed_from_u64(1);
+        let map = board::Board::get_default_board();
+        let spaces: Vec<board::SpaceID> = map.spaces.iter().step_by(7).map(|s| s.id).collect();
+        let players = (0..4).map(|i| {
+            let mut player = PlayerInfo::from(tputil::Player {
+                input: tputil::InputMethod::Virtual(i),
+                color: i,
+                cpu: None,
+            });
+            player.coins = i as u16 * 7;
+            player.stars = i as u8;
+            player.space = spaces[i % 3];
+            player
+        });
+        let game = GameInfo::new(players, map, 20, &mut rng);
+        render::software::assert_golden("game_info", |renderer, trans| {
+            game.render(
+                renderer,
+                trans,
+                BOARD_CENTER,
+                0.06,
+                &mut game::Utils { alpha: 0.0 },
+                &[],
+            );
+        });
     }
 }
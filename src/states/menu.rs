@@ -0,0 +1,148 @@
+use crate::game;
+use crate::render;
+use crate::tputil;
+
+use graphics::Transformed;
+
+pub enum MenuAction {
+    Select(usize),
+    // left or right on the selected row
+    Adjust(usize, i8),
+    Back,
+}
+
+// cursor and edge detection for a vertical list of choices
+pub struct Menu {
+    pub selected: usize,
+    moved: bool,
+    released: bool,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Menu {
+            selected: 0,
+            moved: false,
+            // ignore the press that opened the menu
+            released: false,
+        }
+    }
+
+    // navigate with the combined input of the given controllers
+    pub fn update(
+        &mut self,
+        input: &tputil::InputState,
+        controllers: &[tputil::InputMethod],
+        count: usize,
+    ) -> Option<MenuAction> {
+        let mut movement_x = 0.0;
+        let mut movement_y = 0.0;
+        let mut pressed = false;
+        let mut back = false;
+        for controller in controllers {
+            movement_x += input.get_axis(controller, tputil::Axis::X);
+            movement_y += input.get_axis(controller, tputil::Axis::Y);
+            pressed |= input.is_pressed(controller, tputil::Button::South);
+            back |= input.is_pressed(controller, tputil::Button::East);
+        }
+        self.navigate(movement_x, movement_y, pressed, back, count)
+    }
+
+    // navigate with any connected device
+    pub fn update_any(&mut self, input: &tputil::InputState, count: usize) -> Option<MenuAction> {
+        self.navigate(
+            input.get_axis_any(tputil::Axis::X),
+            input.get_axis_any(tputil::Axis::Y),
+            !input.get_pressed_any(tputil::Button::South).is_empty(),
+            !input.get_pressed_any(tputil::Button::East).is_empty(),
+            count,
+        )
+    }
+
+    fn navigate(
+        &mut self,
+        movement_x: f32,
+        movement_y: f32,
+        pressed: bool,
+        back: bool,
+        count: usize,
+    ) -> Option<MenuAction> {
+        let mut action = None;
+        if movement_x.abs() < 0.5 && movement_y.abs() < 0.5 {
+            self.moved = false;
+        } else if !self.moved {
+            self.moved = true;
+            if movement_x.abs() > movement_y.abs() {
+                let direction = if movement_x > 0.0 { 1 } else { -1 };
+                action = Some(MenuAction::Adjust(self.selected, direction));
+            } else if movement_y > 0.0 {
+                self.selected = (self.selected + count - 1) % count;
+            } else {
+                self.selected = (self.selected + 1) % count;
+            }
+        }
+
+        if !pressed && !back {
+            self.released = true;
+        } else if self.released {
+            self.released = false;
+            action = Some(if pressed {
+                MenuAction::Select(self.selected)
+            } else {
+                MenuAction::Back
+            });
+        }
+        action
+    }
+}
+
+// darken whatever is drawn below an overlay
+pub fn dim(renderer: &mut dyn render::Renderer, trans: graphics::math::Matrix2d) {
+    const DIM: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+    renderer.rectangle(DIM, [-10.0, -10.0, 20.0, 20.0], trans);
+}
+
+pub fn render_choices(
+    title: &str,
+    choices: &[String],
+    selected: usize,
+    description: &str,
+    trans: graphics::math::Matrix2d,
+    renderer: &mut dyn render::Renderer,
+    utils: &mut game::Utils,
+) {
+    const COLOR1: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+    const COLOR2: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+    const ROW_HEIGHT: f64 = 0.1;
+
+    let height = ROW_HEIGHT * (choices.len() + 2) as f64;
+    let top = 0.95 - height;
+    renderer.rectangle(COLOR1, [-0.6, top, 1.2, height], trans);
+    utils.draw_text_align(
+        title,
+        0.07,
+        tputil::Alignment::MIDDLE_CENTER,
+        trans.trans(0.0, top + ROW_HEIGHT / 2.0),
+        renderer,
+    );
+    for (i, choice) in choices.iter().enumerate() {
+        let y = top + ROW_HEIGHT * (i as f64 + 1.5);
+        if i == selected {
+            renderer.rectangle(COLOR2, [-0.6, y - ROW_HEIGHT / 2.0, 1.2, ROW_HEIGHT], trans);
+        }
+        utils.draw_text_align(
+            choice,
+            0.06,
+            tputil::Alignment::MIDDLE_LEFT,
+            trans.trans(-0.55, y),
+            renderer,
+        );
+    }
+    utils.draw_text_align(
+        description,
+        0.04,
+        tputil::Alignment::MIDDLE_CENTER,
+        trans.trans(0.0, top + height - ROW_HEIGHT / 2.0),
+        renderer,
+    );
+}
@@ -1,8 +1,10 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use rand::Rng;
 
 struct CCPlayer {
@@ -18,7 +20,18 @@ pub struct MGCastleClimb {
 }
 
 impl MGCastleClimb {
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
+    pub const TITLE: &'static str = "Climb";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::OneVsThree,
+        MinigameFormat::TwoVsTwo,
+    ];
+    pub fn init(
+        players: Vec<tputil::Player>,
+        _format: MinigameFormat,
+        _rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
         Box::new(MGCastleClimb {
             blocks: vec![tputil::Point2D::ZERO],
             players: players
@@ -44,7 +57,7 @@ impl MGCastleClimb {
 }
 
 impl states::minigame::Minigame for MGCastleClimb {
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.time += props.time;
         let diff =
             tputil::Point2D::new(0.0, props.time * ((-1.0 / (self.time / 50.0 + 1.1)) + 1.0));
@@ -100,7 +113,7 @@ impl states::minigame::Minigame for MGCastleClimb {
         }
         let mut last = self.blocks[self.blocks.len() - 1];
         while last.y > -2.0 {
-            let y = -rand::thread_rng().gen_range(
+            let y = -props.rng.gen_range(
                 MGCastleClimb::MAX_HEIGHT * ((-1.0 / (self.time / 5.0 + 1.0)) + 1.0),
                 MGCastleClimb::MAX_HEIGHT,
             );
@@ -109,9 +122,7 @@ impl states::minigame::Minigame for MGCastleClimb {
                 / 2.0
                 * y;
             let mut x = 1.0 * MGCastleClimb::HORIZ_VEL * t + MGCastleClimb::BLOCK_WIDTH;
-            if x + last.x > 1.0
-                || (last.x - x > -1.0 && rand::thread_rng().gen::<u32>() < std::u32::MAX / 2)
-            {
+            if x + last.x > 1.0 || (last.x - x > -1.0 && props.rng.gen::<u32>() < u32::MAX / 2) {
                 x = -x;
             }
             last += tputil::Point2D::new(x, y);
@@ -121,13 +132,13 @@ impl states::minigame::Minigame for MGCastleClimb {
     }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         _utils: &mut game::Utils,
     ) {
         const COLOR1: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
         for block in &self.blocks {
-            graphics::rectangle(
+            renderer.rectangle(
                 COLOR1,
                 [
                     block.x - MGCastleClimb::BLOCK_WIDTH,
@@ -136,11 +147,10 @@ impl states::minigame::Minigame for MGCastleClimb {
                     MGCastleClimb::BLOCK_HEIGHT * 2.0,
                 ],
                 trans,
-                gl,
             );
         }
         for player in self.players.iter() {
-            graphics::rectangle(
+            renderer.rectangle(
                 tputil::COLORS[player.player.color],
                 graphics::rectangle::centered_square(
                     player.position.x,
@@ -148,14 +158,31 @@ impl states::minigame::Minigame for MGCastleClimb {
                     MGCastleClimb::PLAYER_SIZE,
                 ),
                 trans,
-                gl,
             );
         }
     }
     fn title(&self) -> &'static str {
-        "Climb"
+        MGCastleClimb::TITLE
     }
     fn description(&self) -> &'static str {
         "Move left and right and press A to jump. Ascend faster than your opponents to win."
     }
+    fn bot_input(&self, player: usize) -> cpu::BotInput {
+        let player = &self.players[player];
+        // head for the lowest block that is still above us, jumping whenever we land
+        let feet = player.position.y + MGCastleClimb::PLAYER_SIZE;
+        let target = self
+            .blocks
+            .iter()
+            .filter(|block| block.y < feet - MGCastleClimb::BLOCK_HEIGHT * 2.0)
+            .max_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal));
+        match target {
+            Some(block) => cpu::BotInput {
+                x: ((block.x - player.position.x) * 10.0).clamp(-1.0, 1.0) as f32,
+                south: true,
+                ..Default::default()
+            },
+            None => cpu::BotInput::default(),
+        }
+    }
 }
@@ -1,8 +1,10 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use rand::Rng;
 
 pub struct MGHotRope {
@@ -15,7 +17,18 @@ pub struct MGHotRope {
 }
 
 impl MGHotRope {
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
+    pub const TITLE: &'static str = "Sweeper";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::OneVsThree,
+        MinigameFormat::TwoVsTwo,
+    ];
+    pub fn init(
+        players: Vec<tputil::Player>,
+        _format: MinigameFormat,
+        _rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
         Box::new(MGHotRope::new(players))
     }
     pub fn new(players: Vec<tputil::Player>) -> Self {
@@ -40,14 +53,14 @@ impl MGHotRope {
 impl states::minigame::Minigame for MGHotRope {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         _utils: &mut game::Utils,
     ) {
         let scale = 1.0 / self.players.len() as f64;
         let rope_y = 1.0 - self.rope_time;
         const COLOR1: [f32; 4] = [1.0, 0.5, 0.0, 1.0];
-        graphics::rectangle(COLOR1, [-1.0, rope_y, 2.0, 0.1], trans, gl);
+        renderer.rectangle(COLOR1, [-1.0, rope_y, 2.0, 0.1], trans);
         for i in 0..self.players.len() {
             let y = if self.swept_at[i] < 0.0 {
                 (((self.time - self.jumped_at[i]) * 4.0 - 1.0).powf(2.0) - 1.0).min(0.0) / 2.0
@@ -55,7 +68,7 @@ impl states::minigame::Minigame for MGHotRope {
                 (self.swept_at[i] - self.time) * self.speed
             };
             let color = tputil::COLORS[self.players[i].color];
-            graphics::rectangle(
+            renderer.rectangle(
                 color,
                 [
                     scale * ((2 * i) as f64 + 0.5) - 1.0,
@@ -64,11 +77,10 @@ impl states::minigame::Minigame for MGHotRope {
                     scale,
                 ],
                 trans,
-                gl,
             );
         }
     }
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.time += props.time;
         self.rope_time += props.time * self.speed;
         let mut last_alive: Option<usize> = None;
@@ -100,7 +112,7 @@ impl states::minigame::Minigame for MGHotRope {
         }
 
         if more_than_one && self.rope_time > 2.0 {
-            self.rope_time = -rand::thread_rng().gen_range(0.0, 7.0);
+            self.rope_time = -props.rng.gen_range(0.0, 7.0);
             self.speed *= 1.1;
         }
         if more_than_one || waiting {
@@ -112,9 +124,20 @@ impl states::minigame::Minigame for MGHotRope {
         }
     }
     fn title(&self) -> &'static str {
-        "Sweeper"
+        MGHotRope::TITLE
     }
     fn description(&self) -> &'static str {
         "Press A to jump. Don't let the sweeper sweep you away!"
     }
+    fn bot_input(&self, player: usize) -> cpu::BotInput {
+        // a jump lasts 0.5s and has to cover the whole time the rope is at our feet
+        let until_rope = (1.0 - self.rope_time) / self.speed;
+        let latest_landing = 0.5 - 0.2 / self.speed;
+        cpu::BotInput {
+            south: self.jumped_at[player] < self.time - 0.5
+                && until_rope > 0.0
+                && until_rope < latest_landing - 0.02,
+            ..Default::default()
+        }
+    }
 }
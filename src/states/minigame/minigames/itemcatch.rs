@@ -1,8 +1,10 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use graphics::Transformed;
 use rand::Rng;
 
@@ -36,13 +38,25 @@ pub struct MGItemCatch {
     players: Box<[ICPlayer]>,
     time: f64,
     items: std::vec::Vec<ICItem>,
+    format: MinigameFormat,
 }
 
 impl MGItemCatch {
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
-        Box::new(MGItemCatch::new(players))
+    pub const TITLE: &'static str = "Gold Rush";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::OneVsThree,
+        MinigameFormat::TwoVsTwo,
+    ];
+    pub fn init(
+        players: Vec<tputil::Player>,
+        format: MinigameFormat,
+        _rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
+        Box::new(MGItemCatch::new(players, format))
     }
-    fn new(players: Vec<tputil::Player>) -> Self {
+    fn new(players: Vec<tputil::Player>, format: MinigameFormat) -> Self {
         MGItemCatch {
             players: players
                 .into_iter()
@@ -56,6 +70,26 @@ impl MGItemCatch {
                 .into_boxed_slice(),
             time: 0.0,
             items: std::vec::Vec::new(),
+            format,
+        }
+    }
+    fn team_result(&self) -> MinigameResult {
+        let mut totals = [(0.0, 0); 2];
+        for (index, player) in self.players.iter().enumerate() {
+            let team = &mut totals[self.format.team_of(index)];
+            team.0 += f64::from(player.points);
+            team.1 += 1;
+        }
+        let averages: Vec<f64> = totals
+            .iter()
+            .map(|&(points, count)| points / f64::from(count.max(1)))
+            .collect();
+        if averages[0] > averages[1] {
+            MinigameResult::TeamWinner(0)
+        } else if averages[1] > averages[0] {
+            MinigameResult::TeamWinner(1)
+        } else {
+            MinigameResult::Nothing
         }
     }
     const PLAYER_RADIUS: f64 = 0.06;
@@ -67,30 +101,30 @@ impl MGItemCatch {
 impl states::minigame::Minigame for MGItemCatch {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         const COLOR1: graphics::types::Color = [0.7, 0.7, 0.7, 1.0];
         const COLOR2: graphics::types::Color = [1.0, 0.8, 0.0, 1.0];
         const COLOR3: graphics::types::Color = [1.0, 0.0, 0.0, 1.0];
+        let time = self.time + utils.alpha * game::TICK;
         for item in &self.items {
             let color = if item.value > 0 { COLOR2 } else { COLOR3 };
             let radius = item.get_radius();
-            let pos = item.get_pos(self.time);
-            graphics::rectangle(
+            let pos = item.get_pos(time);
+            renderer.rectangle(
                 color,
                 graphics::rectangle::centered_square(pos.x, pos.y, radius),
                 trans,
-                gl,
             );
         }
-        graphics::rectangle(COLOR1, [-1.5, -1.0, 0.5, 2.0], trans, gl);
-        graphics::rectangle(COLOR1, [1.0, -1.0, 0.5, 2.0], trans, gl);
-        graphics::rectangle(COLOR1, [-1.0, 0.5, 2.0, 0.1], trans, gl);
+        renderer.rectangle(COLOR1, [-1.5, -1.0, 0.5, 2.0], trans);
+        renderer.rectangle(COLOR1, [1.0, -1.0, 0.5, 2.0], trans);
+        renderer.rectangle(COLOR1, [-1.0, 0.5, 2.0, 0.1], trans);
         for player in self.players.iter() {
             let color = tputil::COLORS[player.player.color];
-            graphics::rectangle(
+            renderer.rectangle(
                 color,
                 graphics::rectangle::centered_square(
                     player.position.x,
@@ -98,7 +132,6 @@ impl states::minigame::Minigame for MGItemCatch {
                     MGItemCatch::PLAYER_RADIUS,
                 ),
                 trans,
-                gl,
             );
         }
         let time_left = (MGItemCatch::TIME_LIMIT - self.time).ceil() as i8;
@@ -108,12 +141,15 @@ impl states::minigame::Minigame for MGItemCatch {
             0.1,
             tputil::Alignment::TOP_CENTER,
             trans.trans(0.0, -1.0),
-            gl,
+            renderer,
         );
     }
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.time += props.time;
         if self.time > MGItemCatch::TIME_LIMIT {
+            if self.format.has_teams() {
+                return Some(self.team_result());
+            }
             return Some(MinigameResult::Ratios(
                 self.players
                     .iter()
@@ -165,15 +201,14 @@ impl states::minigame::Minigame for MGItemCatch {
             self.items.remove(index);
         }
         let chance = 1.0 * props.time;
-        if rand::thread_rng().gen::<f64>() < chance {
-            let side = if rand::thread_rng().gen() { 1.0 } else { -1.0 };
+        if props.rng.gen::<f64>() < chance {
+            let side = if props.rng.gen() { 1.0 } else { -1.0 };
             let start_vel = tputil::Point2D::new(
-                rand::thread_rng().gen::<f64>().sqrt() * 2.0 * -side,
-                -rand::thread_rng().gen::<f64>().sqrt(),
+                props.rng.gen::<f64>().sqrt() * 2.0 * -side,
+                -props.rng.gen::<f64>().sqrt(),
             );
-            let start_pos =
-                tputil::Point2D::new(1.25 * side, rand::thread_rng().gen::<f64>() * 1.5 - 1.5);
-            let value = if rand::thread_rng().gen() { 1 } else { -1 };
+            let start_pos = tputil::Point2D::new(1.25 * side, props.rng.gen::<f64>() * 1.5 - 1.5);
+            let value = if props.rng.gen() { 1 } else { -1 };
 
             self.items.push(ICItem {
                 start_pos,
@@ -185,9 +220,42 @@ impl states::minigame::Minigame for MGItemCatch {
         None
     }
     fn title(&self) -> &'static str {
-        "Gold Rush"
+        MGItemCatch::TITLE
     }
     fn description(&self) -> &'static str {
         "Gold is raining down! Move left and right, and jump with A, to collect as much as you can. However, you do not want to catch the red blocks."
     }
+    fn bot_input(&self, player: usize) -> cpu::BotInput {
+        let player = &self.players[player];
+        let falling = |item: &&ICItem| {
+            let pos = item.get_pos(self.time);
+            pos.y < 0.5 && pos.x.abs() < 1.0
+        };
+        let distance = |item: &ICItem| (item.get_pos(self.time).x - player.position.x).abs();
+        let gold = self
+            .items
+            .iter()
+            .filter(|item| item.value > 0)
+            .filter(falling)
+            .min_by(|a, b| {
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        let mut input = cpu::BotInput::default();
+        if let Some(item) = gold {
+            let pos = item.get_pos(self.time);
+            input.x = ((pos.x - player.position.x) * 10.0).clamp(-1.0, 1.0) as f32;
+            input.south = distance(item) < MGItemCatch::PLAYER_RADIUS && pos.y > 0.0;
+        }
+        // dodge red blocks about to land on us
+        for item in self.items.iter().filter(|item| item.value < 0) {
+            let pos = item.get_pos(self.time);
+            if distance(item) < MGItemCatch::PLAYER_RADIUS * 2.0 && pos.y > 0.0 && pos.y < 0.5 {
+                input.x = if pos.x > player.position.x { -1.0 } else { 1.0 };
+                input.south = false;
+            }
+        }
+        input
+    }
 }
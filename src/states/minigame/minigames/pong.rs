@@ -1,8 +1,10 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use rand::Rng;
 use std::f64::consts::PI;
 
@@ -17,6 +19,7 @@ pub struct MGPong {
     time: f64,
     ball_pos: tputil::Point2D,
     ball_vel: tputil::Point2D,
+    format: MinigameFormat,
 }
 
 const WALL_OFFSET: f64 = 0.02;
@@ -25,12 +28,28 @@ const PADDLE_WIDTH: f64 = 0.2;
 const BALL_RADIUS: f64 = 0.03;
 const MAX_BOUNCE_ANGLE: f64 = PI / 3.0;
 const START_SPEED: f64 = 0.8;
+const PADDLE_SPEED: f64 = 0.6;
 
 impl MGPong {
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
-        Box::new(MGPong::new(players))
+    pub const TITLE: &'static str = "Cube Tennis";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::OneVsThree,
+        MinigameFormat::TwoVsTwo,
+    ];
+    pub fn init(
+        players: Vec<tputil::Player>,
+        format: MinigameFormat,
+        rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
+        Box::new(MGPong::new(players, format, rng))
     }
-    pub fn new(players: Vec<tputil::Player>) -> Self {
+    pub fn new(
+        players: Vec<tputil::Player>,
+        format: MinigameFormat,
+        rng: &mut game::GameRng,
+    ) -> Self {
         MGPong {
             players: players
                 .into_iter()
@@ -42,13 +61,14 @@ impl MGPong {
                 .collect::<Vec<_>>()
                 .into_boxed_slice(),
             ball_pos: tputil::Point2D::ZERO,
-            ball_vel: MGPong::random_vel(START_SPEED),
+            ball_vel: MGPong::random_vel(START_SPEED, rng),
             time: 0.0,
+            format,
         }
     }
-    fn random_vel(speed: f64) -> tputil::Point2D {
+    fn random_vel(speed: f64, rng: &mut game::GameRng) -> tputil::Point2D {
         const TAU: f64 = 2.0 * PI;
-        let angle = rand::thread_rng().gen_range(0.0, TAU);
+        let angle = rng.gen_range(0.0, TAU);
         tputil::Point2D::new(angle.cos() * speed, angle.sin() * speed)
     }
     fn get_player_mut(players: &mut [PongPlayer], index: usize) -> Option<&mut PongPlayer> {
@@ -69,17 +89,17 @@ impl MGPong {
 impl states::minigame::Minigame for MGPong {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
-        _: &mut game::Utils,
+        utils: &mut game::Utils,
     ) {
         const COLOR1: graphics::types::Color = [0.0, 0.0, 0.0, 1.0];
         const COLOR2: graphics::types::Color = [0.7, 0.7, 0.7, 1.0];
-        graphics::rectangle(
+        let ball_pos = self.ball_pos + self.ball_vel.multiply_scalar(utils.alpha * game::TICK);
+        renderer.rectangle(
             COLOR1,
-            graphics::rectangle::centered_square(self.ball_pos.x, self.ball_pos.y, BALL_RADIUS),
+            graphics::rectangle::centered_square(ball_pos.x, ball_pos.y, BALL_RADIUS),
             trans,
-            gl,
         );
         //   3
         // 0   1
@@ -132,41 +152,42 @@ impl states::minigame::Minigame for MGPong {
             } else {
                 height = PADDLE_HEIGHT;
             }
-            graphics::rectangle(
+            renderer.rectangle(
                 color,
                 [x - width / 2.0, y - height / 2.0, width, height],
                 trans,
-                gl,
             );
         }
-        graphics::rectangle(COLOR2, [-1.1, -1.0, 0.1, 2.0], trans, gl);
-        graphics::rectangle(COLOR2, [1.0, -1.0, 0.1, 2.0], trans, gl);
-        graphics::rectangle(COLOR2, [-1.0, -1.1, 2.0, 0.1], trans, gl);
-        graphics::rectangle(COLOR2, [-1.0, 1.0, 2.0, 0.1], trans, gl);
+        renderer.rectangle(COLOR2, [-1.1, -1.0, 0.1, 2.0], trans);
+        renderer.rectangle(COLOR2, [1.0, -1.0, 0.1, 2.0], trans);
+        renderer.rectangle(COLOR2, [-1.0, -1.1, 2.0, 0.1], trans);
+        renderer.rectangle(COLOR2, [-1.0, 1.0, 2.0, 0.1], trans);
     }
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.time += props.time;
         self.ball_pos += self.ball_vel.multiply_scalar(props.time);
         let mut multiple_left = false;
         let mut survivor: Option<usize> = None;
         for i in 0..4 {
             let axis;
-            const SCALE: f64 = 0.01;
             let scale;
             if i == 0 || i == 1 {
                 axis = tputil::Axis::Y;
-                scale = -SCALE;
+                scale = -PADDLE_SPEED * props.time;
             } else {
                 axis = tputil::Axis::X;
-                scale = SCALE;
+                scale = PADDLE_SPEED * props.time;
             }
             const WALL_DIST: f64 = 1.0 - WALL_OFFSET - PADDLE_HEIGHT - BALL_RADIUS;
             const OOB_DIST: f64 = 1.0 + BALL_RADIUS;
             if let Some(player) = MGPong::get_player_mut(&mut self.players, i) {
-                if survivor.is_some() {
-                    multiple_left = true;
-                } else {
-                    survivor = Some(i);
+                match survivor {
+                    Some(other) => {
+                        if self.format.team_of(other) != self.format.team_of(i) {
+                            multiple_left = true;
+                        }
+                    }
+                    None => survivor = Some(i),
                 }
                 let last_pos = self.ball_pos;
                 player.position = (player.position
@@ -268,9 +289,33 @@ impl states::minigame::Minigame for MGPong {
         }
     }
     fn title(&self) -> &'static str {
-        "Cube Tennis"
+        MGPong::TITLE
     }
     fn description(&self) -> &'static str {
         "Use the joystick to move your paddle up and down. Don't let the cube get past it."
     }
+    fn bot_input(&self, player: usize) -> cpu::BotInput {
+        let position = match MGPong::get_player(&self.players, player) {
+            Some(p) => p.position,
+            None => return cpu::BotInput::default(),
+        };
+        // side paddles move down for a positive axis, top and bottom ones move right
+        let (target, direction) = if player == 0 || player == 1 {
+            (self.ball_pos.y, -1.0)
+        } else {
+            (self.ball_pos.x, 1.0)
+        };
+        let axis = ((target - position) * 10.0 * direction).clamp(-1.0, 1.0) as f32;
+        if player == 0 || player == 1 {
+            cpu::BotInput {
+                y: axis,
+                ..Default::default()
+            }
+        } else {
+            cpu::BotInput {
+                x: axis,
+                ..Default::default()
+            }
+        }
+    }
 }
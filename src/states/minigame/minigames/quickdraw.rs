@@ -1,8 +1,10 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use graphics::Transformed;
 use rand::Rng;
 
@@ -14,10 +16,20 @@ pub struct MGQuickdraw {
 }
 
 impl MGQuickdraw {
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
-        Box::new(MGQuickdraw::new(players))
+    pub const TITLE: &'static str = "Quickdraw";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::TwoVsTwo,
+    ];
+    pub fn init(
+        players: Vec<tputil::Player>,
+        _format: MinigameFormat,
+        rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
+        Box::new(MGQuickdraw::new(players, rng))
     }
-    fn new(players: Vec<tputil::Player>) -> MGQuickdraw {
+    fn new(players: Vec<tputil::Player>, rng: &mut game::GameRng) -> MGQuickdraw {
         let count = players.len();
         MGQuickdraw {
             players,
@@ -25,7 +37,7 @@ impl MGQuickdraw {
                 .take(count)
                 .collect::<Vec<_>>()
                 .into_boxed_slice(),
-            buzz_time: rand::thread_rng().gen_range(1.0, 10.0),
+            buzz_time: rng.gen_range(1.0, 10.0),
             time: 0.0,
         }
     }
@@ -34,7 +46,7 @@ impl MGQuickdraw {
 impl states::minigame::Minigame for MGQuickdraw {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         _utils: &mut game::Utils,
     ) {
@@ -46,11 +58,10 @@ impl states::minigame::Minigame for MGQuickdraw {
         } else {
             buzzer_color = COLOR1;
         }
-        graphics::rectangle(
+        renderer.rectangle(
             buzzer_color,
             graphics::rectangle::centered([0.0, -0.2, 0.3, 0.5]),
             trans,
-            gl,
         );
         let count = self.players.len();
         let scale = 2.0 / (count + 1) as f64;
@@ -68,15 +79,14 @@ impl states::minigame::Minigame for MGQuickdraw {
                 .trans(x * scale, size)
                 .scale(size, size)
                 .rot_rad(rotation);
-            graphics::rectangle(
+            renderer.rectangle(
                 tputil::COLORS[self.players[i].color],
                 graphics::rectangle::square(0.0, -0.5, 0.5),
                 transform,
-                gl,
             );
         }
     }
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.time += props.time;
         let mut all_dead = true;
         for i in 0..self.players.len() {
@@ -102,9 +112,15 @@ impl states::minigame::Minigame for MGQuickdraw {
         }
     }
     fn title(&self) -> &'static str {
-        "Quickdraw"
+        MGQuickdraw::TITLE
     }
     fn description(&self) -> &'static str {
         "When the big box turns green, press A. The first player to react wins."
     }
+    fn bot_input(&self, _player: usize) -> cpu::BotInput {
+        cpu::BotInput {
+            south: self.time > self.buzz_time,
+            ..Default::default()
+        }
+    }
 }
@@ -1,12 +1,14 @@
+use crate::cpu;
 use crate::game;
+use crate::render;
 use crate::states;
 use crate::tputil;
 
-use crate::states::minigame::MinigameResult;
+use crate::states::minigame::{MinigameFormat, MinigameResult};
 use graphics::Transformed;
 use rand::Rng;
 
-#[derive(Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq)]
 enum Direction {
     North,
     South,
@@ -28,8 +30,18 @@ pub struct MGSnake {
 }
 
 impl MGSnake {
+    pub const TITLE: &'static str = "Snake";
+    pub const FORMATS: &'static [MinigameFormat] = &[
+        MinigameFormat::FreeForAll,
+        MinigameFormat::Duel,
+        MinigameFormat::TwoVsTwo,
+    ];
     const GRID_SIZE: i8 = 32;
-    pub fn init(players: Vec<tputil::Player>) -> Box<dyn states::minigame::Minigame> {
+    pub fn init(
+        players: Vec<tputil::Player>,
+        _format: MinigameFormat,
+        _rng: &mut game::GameRng,
+    ) -> Box<dyn states::minigame::Minigame> {
         let count = players.len();
         let scale = MGSnake::GRID_SIZE / count as i8 / 2;
         let snakes: Vec<Snake> = players
@@ -66,25 +78,42 @@ impl MGSnake {
     }
 }
 
+impl MGSnake {
+    fn step(cell: (i8, i8), direction: Direction) -> (i8, i8) {
+        match direction {
+            Direction::East => (cell.0 + 1, cell.1),
+            Direction::North => (cell.0, cell.1 - 1),
+            Direction::South => (cell.0, cell.1 + 1),
+            Direction::West => (cell.0 - 1, cell.1),
+        }
+    }
+    fn is_free(&self, cell: (i8, i8)) -> bool {
+        cell.0 >= 0
+            && cell.1 >= 0
+            && cell.0 < MGSnake::GRID_SIZE
+            && cell.1 < MGSnake::GRID_SIZE
+            && self.snakes.iter().all(|snake| !snake.tail.contains(&cell))
+    }
+}
+
 impl states::minigame::Minigame for MGSnake {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         _utils: &mut game::Utils,
     ) {
         const COLOR1: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
-        graphics::rectangle(
+        renderer.rectangle(
             COLOR1,
             graphics::rectangle::centered_square(0.0, 0.0, 1.0),
             trans,
-            gl,
         );
         let scale = 2.0 / f64::from(MGSnake::GRID_SIZE);
         let transform = trans.trans(-1.0, -1.0).scale(scale, scale);
         for pellet in &self.pellets {
             const COLOR2: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-            graphics::rectangle(
+            renderer.rectangle(
                 COLOR2,
                 [
                     f64::from(pellet.0) + 0.1,
@@ -93,22 +122,16 @@ impl states::minigame::Minigame for MGSnake {
                     0.8,
                 ],
                 transform,
-                gl,
             );
         }
         for snake in self.snakes.iter() {
             for cube in &snake.tail {
                 let color = tputil::COLORS[snake.player.color];
-                graphics::rectangle(
-                    color,
-                    [cube.0.into(), cube.1.into(), 1.0, 1.0],
-                    transform,
-                    gl,
-                );
+                renderer.rectangle(color, [cube.0.into(), cube.1.into(), 1.0, 1.0], transform);
             }
         }
     }
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult> {
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult> {
         self.unhandled_time += props.time;
 
         for snake in self.snakes.iter_mut() {
@@ -138,10 +161,10 @@ impl states::minigame::Minigame for MGSnake {
         if self.unhandled_time > 0.2 {
             self.unhandled_time -= 0.2;
 
-            if rand::thread_rng().gen::<u32>() < std::u32::MAX / 10 {
+            if props.rng.gen::<u32>() < u32::MAX / 10 {
                 self.pellets.push((
-                    rand::thread_rng().gen_range(0, MGSnake::GRID_SIZE),
-                    rand::thread_rng().gen_range(0, MGSnake::GRID_SIZE),
+                    props.rng.gen_range(0, MGSnake::GRID_SIZE),
+                    props.rng.gen_range(0, MGSnake::GRID_SIZE),
                 ));
             }
 
@@ -229,9 +252,59 @@ impl states::minigame::Minigame for MGSnake {
         None
     }
     fn title(&self) -> &'static str {
-        "Snake"
+        MGSnake::TITLE
     }
     fn description(&self) -> &'static str {
         "Use the joystick to direct your snake. Collect food to grow longer, and avoid running into walls or snakes."
     }
+    fn bot_input(&self, player: usize) -> cpu::BotInput {
+        let snake = &self.snakes[player];
+        let head = match snake.tail.last() {
+            Some(&head) => head,
+            None => return cpu::BotInput::default(),
+        };
+        let options = match snake.direction {
+            Direction::North | Direction::South => {
+                [snake.direction, Direction::West, Direction::East]
+            }
+            Direction::West | Direction::East => {
+                [snake.direction, Direction::North, Direction::South]
+            }
+        };
+        // prefer the way with the most room ahead, then the one closest to food
+        let best = options
+            .iter()
+            .max_by_key(|&&direction| {
+                let mut room = 0;
+                let mut cell = head;
+                while room < 4 {
+                    cell = MGSnake::step(cell, direction);
+                    if !self.is_free(cell) {
+                        break;
+                    }
+                    room += 1;
+                }
+                let next = MGSnake::step(head, direction);
+                let food = self
+                    .pellets
+                    .iter()
+                    .map(|p| i32::from((p.0 - next.0).abs()) + i32::from((p.1 - next.1).abs()))
+                    .min()
+                    .unwrap_or(0);
+                (room, -food)
+            })
+            .copied()
+            .unwrap_or(snake.direction);
+        let mut input = cpu::BotInput::default();
+        match best {
+            Direction::North => input.y = 1.0,
+            Direction::South => input.y = -1.0,
+            Direction::East => input.x = 1.0,
+            Direction::West => input.x = -1.0,
+        }
+        if best == snake.direction {
+            input = cpu::BotInput::default();
+        }
+        input
+    }
 }
@@ -1,40 +1,136 @@
 mod minigames;
 
+use crate::board;
+use crate::cpu;
 use crate::game;
+use crate::render;
+use crate::save;
 use crate::states;
 use crate::tputil;
 
 use graphics::Transformed;
 use rand::Rng;
 
-type MinigameFactory = dyn Fn(Vec<tputil::Player>) -> Box<dyn Minigame> + Sync;
+type MinigameFactory =
+    dyn Fn(Vec<tputil::Player>, MinigameFormat, &mut game::GameRng) -> Box<dyn Minigame> + Sync;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MinigameFormat {
+    FreeForAll,
+    Duel,
+    OneVsThree,
+    TwoVsTwo,
+}
+
+impl MinigameFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            MinigameFormat::FreeForAll => "Free for all",
+            MinigameFormat::Duel => "Duel",
+            MinigameFormat::OneVsThree => "1 vs 3",
+            MinigameFormat::TwoVsTwo => "2 vs 2",
+        }
+    }
+
+    pub fn has_teams(self) -> bool {
+        match self {
+            MinigameFormat::FreeForAll | MinigameFormat::Duel => false,
+            MinigameFormat::OneVsThree | MinigameFormat::TwoVsTwo => true,
+        }
+    }
+
+    // participants are ordered so that teams are contiguous
+    pub fn team_of(self, index: usize) -> usize {
+        match self {
+            MinigameFormat::FreeForAll | MinigameFormat::Duel => index,
+            MinigameFormat::OneVsThree => {
+                if index == 0 {
+                    0
+                } else {
+                    1
+                }
+            }
+            MinigameFormat::TwoVsTwo => index / 2,
+        }
+    }
+}
+
+struct MinigameEntry {
+    title: &'static str,
+    init: Box<MinigameFactory>,
+    formats: &'static [MinigameFormat],
+}
 
 lazy_static::lazy_static! {
-    static ref MINIGAMES: Box<[Box<MinigameFactory>]> = Box::new([
-            Box::new(minigames::quickdraw::MGQuickdraw::init),
-            Box::new(minigames::hotrope::MGHotRope::init),
-            Box::new(minigames::snake::MGSnake::init),
-            Box::new(minigames::castleclimb::MGCastleClimb::init),
-            Box::new(minigames::itemcatch::MGItemCatch::init),
-            Box::new(minigames::pong::MGPong::init),
+    static ref MINIGAMES: Box<[MinigameEntry]> = Box::new([
+            MinigameEntry {
+                title: minigames::quickdraw::MGQuickdraw::TITLE,
+                init: Box::new(minigames::quickdraw::MGQuickdraw::init),
+                formats: minigames::quickdraw::MGQuickdraw::FORMATS,
+            },
+            MinigameEntry {
+                title: minigames::hotrope::MGHotRope::TITLE,
+                init: Box::new(minigames::hotrope::MGHotRope::init),
+                formats: minigames::hotrope::MGHotRope::FORMATS,
+            },
+            MinigameEntry {
+                title: minigames::snake::MGSnake::TITLE,
+                init: Box::new(minigames::snake::MGSnake::init),
+                formats: minigames::snake::MGSnake::FORMATS,
+            },
+            MinigameEntry {
+                title: minigames::castleclimb::MGCastleClimb::TITLE,
+                init: Box::new(minigames::castleclimb::MGCastleClimb::init),
+                formats: minigames::castleclimb::MGCastleClimb::FORMATS,
+            },
+            MinigameEntry {
+                title: minigames::itemcatch::MGItemCatch::TITLE,
+                init: Box::new(minigames::itemcatch::MGItemCatch::init),
+                formats: minigames::itemcatch::MGItemCatch::FORMATS,
+            },
+            MinigameEntry {
+                title: minigames::pong::MGPong::TITLE,
+                init: Box::new(minigames::pong::MGPong::init),
+                formats: minigames::pong::MGPong::FORMATS,
+            },
     ]);
 }
 
+// indices into MINIGAMES of the games everyone can play together
+pub fn free_play_minigames() -> Vec<(usize, &'static str)> {
+    MINIGAMES
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.formats.contains(&MinigameFormat::FreeForAll))
+        .map(|(i, entry)| (i, entry.title))
+        .collect()
+}
+
+#[derive(Copy, Clone)]
+pub enum MinigameKind {
+    Round,
+    FreePlay,
+    Duel { turn: usize, wager: u16 },
+}
+
 pub trait Minigame {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     );
-    fn update(&mut self, props: &game::UpdateProps<'_>) -> Option<MinigameResult>;
+    fn update(&mut self, props: &mut game::UpdateProps<'_>) -> Option<MinigameResult>;
     fn title(&self) -> &'static str;
     fn description(&self) -> &'static str;
+    // what a perfect player would be doing right now
+    fn bot_input(&self, player: usize) -> cpu::BotInput;
 }
 
 pub enum MinigameResult {
     Nothing,
     Winner(usize),
+    TeamWinner(usize),
     Tie(Box<[usize]>),
     Ratios(Box<[f64]>),
 }
@@ -42,25 +138,48 @@ pub enum MinigameResult {
 pub struct MinigameState {
     minigame: Box<dyn Minigame>,
     game: states::ingame::GameInfo,
+    participants: Vec<usize>,
+    format: MinigameFormat,
+    kind: MinigameKind,
+    bots: Vec<cpu::Bot>,
 }
 
 impl game::State for MinigameState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
-        self.minigame.render(gl, trans, utils);
+        self.minigame.render(renderer, trans, utils);
     }
-    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
-        let result = self.minigame.update(&props);
+    fn update(&mut self, mut props: game::UpdateProps<'_>) -> game::UpdateResult {
+        for bot in &mut self.bots {
+            let wanted = self.minigame.bot_input(bot.player);
+            let input = bot.update(wanted, props.time, props.rng);
+            props
+                .input
+                .set_virtual_axis(bot.id, tputil::Axis::X, input.x);
+            props
+                .input
+                .set_virtual_axis(bot.id, tputil::Axis::Y, input.y);
+            props
+                .input
+                .set_virtual_button(bot.id, tputil::Button::South, input.south);
+        }
+        let result = self.minigame.update(&mut props);
         if let Some(result) = result {
             println!("returned from minigame");
+            for bot in &self.bots {
+                props.input.remove_virtual(bot.id);
+            }
             let processed = self.process_result(result);
 
             crate::to_new_state!(move |prev: Self| {
-                Box::new(MinigameResultState::new(prev.game, processed))
+                Box::new(MinigameResultState::new(prev.game, processed, prev.kind))
             })
         } else {
             game::UpdateResult::Continue
@@ -71,17 +190,48 @@ impl game::State for MinigameState {
 impl MinigameState {
     const MINIGAME_COINS: i16 = 10;
     fn process_result(&self, result: MinigameResult) -> Box<[i16]> {
+        let local = match self.kind {
+            MinigameKind::Round | MinigameKind::FreePlay => self.process_round_result(result),
+            MinigameKind::Duel { wager, .. } => self.process_duel_result(result, wager),
+        };
+        let mut tr = vec![0; self.game.players.len()].into_boxed_slice();
+        for (i, &index) in self.participants.iter().enumerate() {
+            tr[index] = local[i];
+        }
+        tr
+    }
+    fn process_duel_result(&self, result: MinigameResult, wager: u16) -> Box<[i16]> {
+        let winner = match result {
+            MinigameResult::Winner(index) | MinigameResult::TeamWinner(index) => Some(index),
+            MinigameResult::Ratios(ref ratios) if ratios[0] > ratios[1] => Some(0),
+            MinigameResult::Ratios(ref ratios) if ratios[1] > ratios[0] => Some(1),
+            _ => None,
+        };
+        let mut tr = Box::new([0, 0]);
+        if let Some(winner) = winner {
+            let loser = 1 - winner;
+            let amount = wager.min(self.game.players[self.participants[loser]].coins) as i16;
+            tr[winner] = amount;
+            tr[loser] = -amount;
+        }
+        tr
+    }
+    fn process_round_result(&self, result: MinigameResult) -> Box<[i16]> {
+        let result = match result {
+            MinigameResult::Winner(index) if self.format.has_teams() => {
+                MinigameResult::TeamWinner(self.format.team_of(index))
+            }
+            result => result,
+        };
         match result {
             MinigameResult::Nothing => self
-                .game
-                .players
+                .participants
                 .iter()
                 .map(|_| 0)
                 .collect::<std::vec::Vec<i16>>()
                 .into_boxed_slice(),
             MinigameResult::Winner(index) => self
-                .game
-                .players
+                .participants
                 .iter()
                 .enumerate()
                 .map(|(i, _)| {
@@ -93,11 +243,26 @@ impl MinigameState {
                 })
                 .collect::<std::vec::Vec<i16>>()
                 .into_boxed_slice(),
+            MinigameResult::TeamWinner(team) => {
+                let members = (0..self.participants.len())
+                    .filter(|&i| self.format.team_of(i) == team)
+                    .count();
+                let amount = MinigameState::MINIGAME_COINS / members.max(1) as i16;
+                (0..self.participants.len())
+                    .map(|i| {
+                        if self.format.team_of(i) == team {
+                            amount
+                        } else {
+                            0
+                        }
+                    })
+                    .collect::<std::vec::Vec<i16>>()
+                    .into_boxed_slice()
+            }
             MinigameResult::Tie(indices) => {
                 let amount = MinigameState::MINIGAME_COINS / indices.len() as i16;
                 let mut tr = self
-                    .game
-                    .players
+                    .participants
                     .iter()
                     .map(|_| 0)
                     .collect::<std::vec::Vec<i16>>()
@@ -124,7 +289,7 @@ impl MinigameState {
                     .enumerate()
                     .map(|(i, x)| {
                         (x * scale)
-                            .max(-f64::from(self.game.players[i].coins))
+                            .max(-f64::from(self.game.players[self.participants[i]].coins))
                             .trunc() as i16
                     })
                     .collect::<std::vec::Vec<i16>>()
@@ -132,8 +297,34 @@ impl MinigameState {
             }
         }
     }
-    pub fn new(game: states::ingame::GameInfo, minigame: Box<dyn Minigame>) -> MinigameState {
-        MinigameState { game, minigame }
+    pub fn new(
+        game: states::ingame::GameInfo,
+        minigame: Box<dyn Minigame>,
+        participants: Vec<usize>,
+        format: MinigameFormat,
+        kind: MinigameKind,
+    ) -> MinigameState {
+        let bots = participants
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &index)| {
+                let player = &game.players[index].player;
+                match (player.input, player.cpu) {
+                    (tputil::InputMethod::Virtual(id), Some(difficulty)) => {
+                        Some(cpu::Bot::new(i, id, difficulty))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        MinigameState {
+            game,
+            minigame,
+            participants,
+            format,
+            kind,
+            bots,
+        }
     }
 }
 
@@ -141,29 +332,38 @@ struct MinigameResultState {
     game: states::ingame::GameInfo,
     time: f64,
     result: Box<[i16]>,
+    kind: MinigameKind,
 }
 
 impl MinigameResultState {
-    pub fn new(game: states::ingame::GameInfo, result: Box<[i16]>) -> MinigameResultState {
+    pub fn new(
+        game: states::ingame::GameInfo,
+        result: Box<[i16]>,
+        kind: MinigameKind,
+    ) -> MinigameResultState {
         MinigameResultState {
             game,
             result,
+            kind,
             time: 0.0,
         }
     }
 }
 
 impl game::State for MinigameResultState {
+    fn pausable(&self) -> bool {
+        true
+    }
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
         let scale = 2.0 / self.game.players.len() as f64;
         for i in 0..self.game.players.len() {
             let color = tputil::COLORS[self.game.players[i].player.color];
-            graphics::rectangle(
+            renderer.rectangle(
                 color,
                 graphics::rectangle::centered_square(
                     scale / 2.0 - 1.0,
@@ -171,14 +371,13 @@ impl game::State for MinigameResultState {
                     scale / 3.0,
                 ),
                 trans,
-                gl,
             );
             utils.draw_text_align(
                 &format!("{:+}", self.result[i]),
                 scale / 2.0,
                 tputil::Alignment::MIDDLE_LEFT,
                 trans.trans((scale * 11.0 / 12.0) - 1.0, (i as f64 + 0.5) * scale - 1.0),
-                gl,
+                renderer,
             );
         }
     }
@@ -187,8 +386,39 @@ impl game::State for MinigameResultState {
         if self.time > 3.0 {
             let mut new_game_state = self.game.clone();
             for (i, player) in new_game_state.players.iter_mut().enumerate() {
-                player.coins = (player.coins as i16 + self.result[i]) as u16;
+                player.add_coins(i32::from(self.result[i]));
+                if self.result[i] > 0 {
+                    player.stats.minigame_coins += self.result[i] as u16;
+                }
+            }
+            match self.kind {
+                MinigameKind::Duel { turn, .. } => {
+                    return game::UpdateResult::NewState(Box::new(
+                        states::ingame::SpaceResultState::new(new_game_state, turn),
+                    ));
+                }
+                MinigameKind::FreePlay => {
+                    return game::UpdateResult::NewState(Box::new(
+                        states::freeplay::FreePlayState::new(new_game_state),
+                    ));
+                }
+                MinigameKind::Round => {}
+            }
+            if new_game_state.is_last_turn() {
+                // the party is over once the last turn is, bonus stars or not,
+                // so there is nothing left to continue
+                save::delete();
+                if !props.settings.bonus_stars {
+                    return game::UpdateResult::NewState(Box::new(
+                        states::ingame::FinalResultsState::new(new_game_state),
+                    ));
+                }
+                return game::UpdateResult::NewState(Box::new(states::bonus::BonusStarState::new(
+                    new_game_state,
+                )));
             }
+            new_game_state.turn += 1;
+            save::autosave(&new_game_state);
             return game::UpdateResult::NewState(Box::new(states::ingame::DieRollState::new(
                 new_game_state,
                 0,
@@ -201,19 +431,121 @@ impl game::State for MinigameResultState {
 pub struct MinigameDescriptionState {
     game: states::ingame::GameInfo,
     minigame: Box<dyn Minigame>,
+    participants: Vec<usize>,
+    format: MinigameFormat,
+    kind: MinigameKind,
 }
 
 impl MinigameDescriptionState {
-    pub fn new_random(game: states::ingame::GameInfo) -> MinigameDescriptionState {
-        let players: Vec<_> = game
-            .players
+    pub fn new_random(
+        game: states::ingame::GameInfo,
+        rng: &mut game::GameRng,
+    ) -> MinigameDescriptionState {
+        let (format, participants) = MinigameDescriptionState::round_format(&game, rng);
+        MinigameDescriptionState::new(game, participants, format, MinigameKind::Round, rng)
+    }
+
+    pub fn new_duel(
+        game: states::ingame::GameInfo,
+        turn: usize,
+        opponent: usize,
+        wager: u16,
+        rng: &mut game::GameRng,
+    ) -> MinigameDescriptionState {
+        MinigameDescriptionState::new(
+            game,
+            vec![turn, opponent],
+            MinigameFormat::Duel,
+            MinigameKind::Duel { turn, wager },
+            rng,
+        )
+    }
+
+    pub fn new_free_play(
+        game: states::ingame::GameInfo,
+        minigame: usize,
+        rng: &mut game::GameRng,
+    ) -> MinigameDescriptionState {
+        let participants = (0..game.players.len()).collect();
+        MinigameDescriptionState::with_entry(
+            game,
+            &MINIGAMES[minigame],
+            participants,
+            MinigameFormat::FreeForAll,
+            MinigameKind::FreePlay,
+            rng,
+        )
+    }
+
+    fn new(
+        game: states::ingame::GameInfo,
+        participants: Vec<usize>,
+        format: MinigameFormat,
+        kind: MinigameKind,
+        rng: &mut game::GameRng,
+    ) -> MinigameDescriptionState {
+        let choices: Vec<&MinigameEntry> = MINIGAMES
+            .iter()
+            .filter(|entry| entry.formats.contains(&format))
+            .collect();
+        let entry = choices[rng.gen_range(0, choices.len())];
+        MinigameDescriptionState::with_entry(game, entry, participants, format, kind, rng)
+    }
+
+    fn with_entry(
+        game: states::ingame::GameInfo,
+        entry: &MinigameEntry,
+        participants: Vec<usize>,
+        format: MinigameFormat,
+        kind: MinigameKind,
+        rng: &mut game::GameRng,
+    ) -> MinigameDescriptionState {
+        let players: Vec<_> = participants
             .iter()
-            .map(|player| player.player.clone())
+            .map(|&index| game.players[index].player.clone())
             .collect();
 
         MinigameDescriptionState {
             game,
-            minigame: MINIGAMES[rand::thread_rng().gen_range(0, MINIGAMES.len())](players),
+            minigame: (entry.init)(players, format, rng),
+            participants,
+            format,
+            kind,
+        }
+    }
+
+    // split players by the color of the space they landed on this round
+    fn round_format(
+        game: &states::ingame::GameInfo,
+        rng: &mut game::GameRng,
+    ) -> (MinigameFormat, Vec<usize>) {
+        let everyone = (0..game.players.len()).collect();
+        if game.players.len() != 4 {
+            return (MinigameFormat::FreeForAll, everyone);
+        }
+
+        let (positive, negative): (Vec<usize>, Vec<usize>) =
+            (0..game.players.len()).partition(|&i| match game.players[i].landed {
+                Some(board::SpaceType::Positive) => true,
+                Some(board::SpaceType::Negative) => false,
+                _ => rng.gen(),
+            });
+        let (format, participants) = match (positive.len(), negative.len()) {
+            (1, _) => (MinigameFormat::OneVsThree, [positive, negative].concat()),
+            (_, 1) => (MinigameFormat::OneVsThree, [negative, positive].concat()),
+            (2, 2) => (MinigameFormat::TwoVsTwo, [positive, negative].concat()),
+            _ => (MinigameFormat::FreeForAll, everyone),
+        };
+        if MINIGAMES
+            .iter()
+            .any(|entry| entry.formats.contains(&format))
+        {
+            (format, participants)
+        } else {
+            (
+                MinigameFormat::FreeForAll,
+                (0..game.players.len()).collect(),
+            )
         }
     }
 }
@@ -221,7 +553,7 @@ impl MinigameDescriptionState {
 impl game::State for MinigameDescriptionState {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
         utils: &mut game::Utils,
     ) {
@@ -230,16 +562,40 @@ impl game::State for MinigameDescriptionState {
             0.15,
             tputil::Alignment::TOP_CENTER,
             trans.trans(0.0, -1.0),
-            gl,
+            renderer,
         );
 
+        let subtitle = match self.kind {
+            MinigameKind::Duel { wager, .. } => format!("Duel for {} coins!", wager),
+            MinigameKind::Round | MinigameKind::FreePlay => self.format.name().to_owned(),
+        };
+        utils.draw_text_align(
+            &subtitle,
+            0.07,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -0.75),
+            renderer,
+        );
+        if self.format.has_teams() {
+            let count = self.participants.len();
+            for (i, &index) in self.participants.iter().enumerate() {
+                let team = self.format.team_of(i) as f64;
+                let x = (i as f64 - (count - 1) as f64 / 2.0) * 0.15 + (team - 0.5) * 0.15;
+                renderer.rectangle(
+                    tputil::COLORS[self.game.players[index].player.color],
+                    graphics::rectangle::centered_square(x, -0.5, 0.05),
+                    trans,
+                );
+            }
+        }
+
         utils.draw_text_align_wrap(
             self.minigame.description(),
             0.05,
             tputil::Alignment::MIDDLE_CENTER,
             1.5,
             trans.trans(0.0, 0.0),
-            gl,
+            renderer,
         );
 
         utils.draw_text_align(
@@ -247,7 +603,7 @@ impl game::State for MinigameDescriptionState {
             0.1,
             tputil::Alignment::BOTTOM_CENTER,
             trans.trans(0.0, 1.0),
-            gl,
+            renderer,
         );
     }
 
@@ -258,10 +614,61 @@ impl game::State for MinigameDescriptionState {
             .is_empty()
         {
             crate::to_new_state!(move |prev: Self| {
-                Box::new(MinigameState::new(prev.game, prev.minigame))
+                Box::new(MinigameState::new(
+                    prev.game,
+                    prev.minigame,
+                    prev.participants,
+                    prev.format,
+                    prev.kind,
+                ))
             })
         } else {
             game::UpdateResult::Continue
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use rand::SeedableRng;
+
+    #[test]
+    fn minigames_match_golden_images() {
+        for entry in MINIGAMES.iter() {
+            let format = entry.formats[0];
+            let count = match format {
+                MinigameFormat::Duel => 2,
+                _ => 4,
+            };
+            let players = (0..count)
+                .map(|i| tputil::Player {
+                    input: tputil::InputMethod::Virtual(i),
+                    color: i,
+                    cpu: None,
+                })
+                .collect();
+            let mut rng = game::GameRng::seed_from_u64(1);
+            let mut minigame = (entry.init)(players, format, &mut rng);
+            let mut input = tputil::InputState::without_gilrs();
+            let mut settings = settings::Settings::default();
+            // a second in, so moving parts have moved
+            for _ in 0..60 {
+                let mut props = game::UpdateProps {
+                    input: &mut input,
+                    rng: &mut rng,
+                    settings: &mut settings,
+                    time: game::TICK,
+                };
+                if minigame.update(&mut props).is_some() {
+                    break;
+                }
+            }
+            let name = format!("minigame_{}", entry.title.to_lowercase().replace(' ', "_"));
+            render::software::assert_golden(&name, |renderer, trans| {
+                minigame.render(renderer, trans, &mut game::Utils { alpha: 0.0 });
+            });
+        }
+    }
+}
@@ -1,3 +1,12 @@
+pub mod bonus;
+pub mod controls;
+pub mod duel;
+pub mod event;
+pub mod freeplay;
 pub mod ingame;
+pub mod menu;
 pub mod minigame;
+pub mod pause;
+pub mod settings;
 pub mod setup;
+pub mod shop;
@@ -0,0 +1,135 @@
+use crate::game;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+const PAUSE_CHOICES: [&str; 3] = ["Resume", "Settings", "Quit to menu"];
+const CONFIRM_CHOICES: [&str; 2] = ["Keep playing", "Abandon party"];
+
+pub fn pause_pressed(input: &tputil::InputState) -> bool {
+    !input.get_pressed_any(tputil::Button::Start).is_empty()
+        || input.is_key_pressed(piston::input::keyboard::Key::Escape)
+}
+
+fn any_pressed(input: &tputil::InputState) -> bool {
+    pause_pressed(input)
+        || !input.get_pressed_any(tputil::Button::South).is_empty()
+        || !input.get_pressed_any(tputil::Button::East).is_empty()
+}
+
+fn to_strings(choices: &[&str]) -> Vec<String> {
+    choices.iter().map(|&choice| choice.to_owned()).collect()
+}
+
+pub struct PauseState {
+    menu: states::menu::Menu,
+    // the pause button has to be let go before it can resume the game
+    pause_released: bool,
+    resuming: bool,
+}
+
+impl PauseState {
+    pub fn new() -> Self {
+        PauseState {
+            menu: states::menu::Menu::new(),
+            pause_released: false,
+            resuming: false,
+        }
+    }
+}
+
+impl game::State for PauseState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        states::menu::dim(renderer, trans);
+        states::menu::render_choices(
+            "Paused",
+            &to_strings(&PAUSE_CHOICES),
+            self.menu.selected,
+            "",
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        // don't hand the resuming press to the paused state
+        if self.resuming {
+            if any_pressed(props.input) {
+                return game::UpdateResult::Continue;
+            }
+            return game::UpdateResult::Pop;
+        }
+
+        let pause = pause_pressed(props.input);
+        if !pause {
+            self.pause_released = true;
+        } else if self.pause_released {
+            self.resuming = true;
+            return game::UpdateResult::Continue;
+        }
+
+        match self.menu.update_any(props.input, PAUSE_CHOICES.len()) {
+            Some(states::menu::MenuAction::Select(0)) | Some(states::menu::MenuAction::Back) => {
+                self.resuming = true;
+            }
+            Some(states::menu::MenuAction::Select(1)) => {
+                return game::UpdateResult::Push(Box::new(states::settings::SettingsState::new(
+                    props.settings.clone(),
+                )));
+            }
+            Some(states::menu::MenuAction::Select(_)) => {
+                return game::UpdateResult::Push(Box::new(ConfirmQuitState::new()));
+            }
+            _ => {}
+        }
+
+        game::UpdateResult::Continue
+    }
+}
+
+struct ConfirmQuitState {
+    menu: states::menu::Menu,
+}
+
+impl ConfirmQuitState {
+    fn new() -> Self {
+        ConfirmQuitState {
+            menu: states::menu::Menu::new(),
+        }
+    }
+}
+
+impl game::State for ConfirmQuitState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        states::menu::render_choices(
+            "Abandon the party?",
+            &to_strings(&CONFIRM_CHOICES),
+            self.menu.selected,
+            "Saved parties continue from the start of the round.",
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        match self.menu.update_any(props.input, CONFIRM_CHOICES.len()) {
+            Some(states::menu::MenuAction::Select(1)) => {
+                game::UpdateResult::NewState(Box::new(states::setup::MenuState::new()))
+            }
+            Some(states::menu::MenuAction::Select(_)) | Some(states::menu::MenuAction::Back) => {
+                game::UpdateResult::Pop
+            }
+            _ => game::UpdateResult::Continue,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::cpu;
+use crate::game;
+use crate::render;
+use crate::settings;
+use crate::states;
+
+pub struct SettingsState {
+    settings: settings::Settings,
+    menu: states::menu::Menu,
+}
+
+impl SettingsState {
+    pub fn new(settings: settings::Settings) -> Self {
+        SettingsState {
+            settings,
+            menu: states::menu::Menu::new(),
+        }
+    }
+
+    fn choices(&self) -> Vec<String> {
+        vec![
+            format!("Party length: {} turns", self.settings.turns),
+            format!(
+                "Bonus stars: {}",
+                if self.settings.bonus_stars {
+                    "On"
+                } else {
+                    "Off"
+                }
+            ),
+            format!("CPU difficulty: {}", self.settings.cpu_difficulty.name()),
+            "Controls".to_owned(),
+            "Back".to_owned(),
+        ]
+    }
+
+    fn change(&mut self, row: usize, direction: i8) -> bool {
+        match row {
+            0 => {
+                self.settings.turns = if direction > 0 {
+                    (self.settings.turns + 1).min(settings::MAX_TURNS)
+                } else {
+                    (self.settings.turns - 1).max(settings::MIN_TURNS)
+                }
+            }
+            1 => self.settings.bonus_stars = !self.settings.bonus_stars,
+            2 => {
+                let levels = &cpu::Difficulty::ALL;
+                let current = levels
+                    .iter()
+                    .position(|&level| level == self.settings.cpu_difficulty)
+                    .unwrap_or(0);
+                let next = if direction > 0 {
+                    (current + 1).min(levels.len() - 1)
+                } else {
+                    current.saturating_sub(1)
+                };
+                self.settings.cpu_difficulty = levels[next];
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl game::State for SettingsState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        states::menu::dim(renderer, trans);
+        let description = match self.menu.selected {
+            0 => "How many turns new parties last.",
+            1 => "Award extra stars for statistics at the end of a party.",
+            2 => "How well CPU players added in the join screen play.",
+            3 => "Change the keys and buttons of each keyboard player and controller.",
+            _ => "",
+        };
+        states::menu::render_choices(
+            "Settings",
+            &self.choices(),
+            self.menu.selected,
+            description,
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let count = self.choices().len();
+        let changed = match self.menu.update_any(props.input, count) {
+            Some(states::menu::MenuAction::Adjust(row, direction)) => self.change(row, direction),
+            Some(states::menu::MenuAction::Select(row)) => {
+                if row + 1 == count {
+                    return game::UpdateResult::Pop;
+                }
+                if row == 3 {
+                    return game::UpdateResult::Push(Box::new(
+                        states::controls::ControlsState::new(props.input.bindings.clone()),
+                    ));
+                }
+                // only toggles change on press, numbers use left/right
+                row == 1 && self.change(row, 1)
+            }
+            Some(states::menu::MenuAction::Back) => return game::UpdateResult::Pop,
+            None => false,
+        };
+        if changed {
+            *props.settings = self.settings.clone();
+            props.settings.save();
+        }
+
+        game::UpdateResult::Continue
+    }
+}
@@ -1,32 +1,222 @@
 use crate::board;
+use crate::cpu;
 use crate::game;
+use crate::render;
+use crate::save;
+use crate::settings;
 use crate::states;
 use crate::tputil;
 
 use graphics::Transformed;
 use rand::Rng;
 
-pub struct MenuState {}
+const MENU_CHOICES: [&str; 5] = [
+    "New party",
+    "Continue game",
+    "Minigame free play",
+    "Settings",
+    "Quit",
+];
+
+pub struct MenuState {
+    menu: states::menu::Menu,
+    message: Option<String>,
+}
+
+impl MenuState {
+    pub fn new() -> Self {
+        MenuState {
+            menu: states::menu::Menu::new(),
+            message: None,
+        }
+    }
+}
 
 impl game::State for MenuState {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
-        _: &mut game::Utils,
+        utils: &mut game::Utils,
     ) {
-        const COLOR1: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
-        graphics::rectangle(
-            COLOR1,
-            graphics::rectangle::centered_square(0.0, 0.0, 0.1),
+        let choices: Vec<String> = MENU_CHOICES
+            .iter()
+            .map(|&choice| choice.to_owned())
+            .collect();
+        let description = match self.message {
+            Some(ref message) => message.as_str(),
+            None => match self.menu.selected {
+                0 => "Start a new party on a board of your choice.",
+                1 => "Resume the party that was saved at the start of its last round.",
+                2 => "Play any minigame without a board.",
+                3 => "Change how parties are played.",
+                _ => "Leave the game.",
+            },
+        };
+        states::menu::render_choices(
+            "Tuxparty",
+            &choices,
+            self.menu.selected,
+            description,
             trans,
-            gl,
+            renderer,
+            utils,
         );
     }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let previous = self.menu.selected;
+        let action = self.menu.update_any(props.input, MENU_CHOICES.len());
+        if self.menu.selected != previous {
+            self.message = None;
+        }
+        if let Some(states::menu::MenuAction::Select(selected)) = action {
+            match selected {
+                0 => return game::UpdateResult::NewState(Box::new(JoinState::new(false))),
+                1 => {
+                    if !save::exists() {
+                        self.message = Some("There is no saved party to continue.".to_owned());
+                        return game::UpdateResult::Continue;
+                    }
+                    match save::load() {
+                        Ok(game) => {
+                            return game::UpdateResult::NewState(Box::new(ResumeState::new(
+                                game,
+                                props.input,
+                            )))
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to load {}: {}", save::SAVE_PATH, err);
+                            self.message = Some(format!("Could not load the saved party: {}", err));
+                        }
+                    }
+                }
+                2 => return game::UpdateResult::NewState(Box::new(JoinState::new(true))),
+                3 => {
+                    return game::UpdateResult::Push(Box::new(
+                        states::settings::SettingsState::new(props.settings.clone()),
+                    ))
+                }
+                _ => return game::UpdateResult::Quit,
+            }
+        }
+
+        game::UpdateResult::Continue
+    }
+}
+
+pub struct ResumeState {
+    game: states::ingame::GameInfo,
+    // index of the next player waiting for a controller
+    waiting: usize,
+    released: bool,
+}
+
+impl ResumeState {
+    fn new(mut game: states::ingame::GameInfo, input: &mut tputil::InputState) -> Self {
+        for info in &mut game.players {
+            if info.player.cpu.is_some() {
+                info.player.input = input.new_virtual();
+            }
+        }
+        let mut state = ResumeState {
+            game,
+            waiting: 0,
+            released: false,
+        };
+        state.skip_cpus();
+        state
+    }
+
+    fn skip_cpus(&mut self) {
+        while self
+            .game
+            .players
+            .get(self.waiting)
+            .and_then(|info| info.player.cpu)
+            .is_some()
+        {
+            self.waiting += 1;
+        }
+    }
+}
+
+impl game::State for ResumeState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        const COLOR1: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+        let count = self.game.players.len();
+        let scale = 2.0 / (count + 1) as f64;
+
+        utils.draw_text_align(
+            &format!(
+                "{} - turn {} of {}",
+                self.game.map.name, self.game.turn, self.game.max_turns
+            ),
+            0.08,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -0.9),
+            renderer,
+        );
+        for (i, player) in self.game.players.iter().enumerate() {
+            let transform = trans.trans(scale * (i as f64 + 1.0) - 1.0, 0.0);
+            if i == self.waiting {
+                renderer.rectangle(
+                    COLOR1,
+                    graphics::rectangle::centered_square(0.0, 0.0, scale / 4.0 + 0.02),
+                    transform,
+                );
+            }
+            let size = if i < self.waiting || player.player.cpu.is_some() {
+                scale / 4.0
+            } else {
+                scale / 8.0
+            };
+            renderer.rectangle(
+                tputil::COLORS[player.player.color],
+                graphics::rectangle::centered_square(0.0, 0.0, size),
+                transform,
+            );
+        }
+        if self.waiting < count {
+            let player = &self.game.players[self.waiting].player;
+            utils.draw_text_align(
+                &format!(
+                    "Press South to play as {}",
+                    tputil::COLOR_NAMES[player.color]
+                ),
+                0.07,
+                tputil::Alignment::BOTTOM_CENTER,
+                trans.trans(0.0, 0.9),
+                renderer,
+            );
+        }
+    }
     fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
         let pressed = props.input.get_pressed_any(tputil::Button::South);
-        if !pressed.is_empty() {
-            return game::UpdateResult::NewState(Box::new(JoinState::new()));
+        // wait for the press that chose "Continue game" to end
+        if !self.released {
+            self.released = pressed.is_empty();
+            return game::UpdateResult::Continue;
+        }
+        for input in pressed {
+            let taken = self.game.players[..self.waiting]
+                .iter()
+                .any(|info| info.player.input == input);
+            if self.waiting < self.game.players.len() && !taken {
+                self.game.players[self.waiting].player.input = input;
+                self.waiting += 1;
+                self.skip_cpus();
+            }
+        }
+
+        if self.waiting >= self.game.players.len() {
+            return crate::to_new_state!(|prev: Self| Box::new(states::ingame::DieRollState::new(
+                prev.game, 0
+            )));
         }
 
         game::UpdateResult::Continue
@@ -37,15 +227,21 @@ impl game::State for MenuState {
 struct JoinStatePlayer {
     player: tputil::Player,
     rotation: f64,
+    moved: bool,
+    // leaving takes a fresh press of East, not the one that backed out of the board selection
+    can_leave: bool,
 }
 
 impl JoinStatePlayer {
-    fn new(player: tputil::InputMethod, color: usize) -> Self {
+    fn new(player: tputil::InputMethod, color: usize, cpu: Option<cpu::Difficulty>) -> Self {
         JoinStatePlayer {
             rotation: 0.0,
+            moved: false,
+            can_leave: true,
             player: tputil::Player {
                 input: player,
                 color,
+                cpu,
             },
         }
     }
@@ -53,41 +249,90 @@ impl JoinStatePlayer {
 
 pub struct JoinState {
     players: Vec<JoinStatePlayer>,
+    free_play: bool,
 }
 
 impl JoinState {
-    fn new() -> Self {
+    pub fn new(free_play: bool) -> Self {
         JoinState {
             players: Vec::new(),
+            free_play,
         }
     }
+
+    fn rejoin(players: Vec<tputil::Player>) -> Self {
+        JoinState {
+            players: players
+                .into_iter()
+                .map(|player| JoinStatePlayer {
+                    can_leave: false,
+                    ..JoinStatePlayer::new(player.input, player.color, player.cpu)
+                })
+                .collect(),
+            free_play: false,
+        }
+    }
+
+    fn free_color(&self, rng: &mut game::GameRng) -> usize {
+        let mut colors: Vec<usize> = (0..tputil::COLORS.len()).collect();
+        for player in &self.players {
+            if let Some(pos) = colors.iter().position(|&c| c == player.player.color) {
+                colors.remove(pos);
+            }
+        }
+        colors[rng.gen_range(0, colors.len())]
+    }
 }
 
 impl game::State for JoinState {
     fn render(
         &self,
-        gl: &mut opengl_graphics::GlGraphics,
+        renderer: &mut dyn render::Renderer,
         trans: graphics::math::Matrix2d,
-        _: &mut game::Utils,
+        utils: &mut game::Utils,
     ) {
         const COLOR1: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+        const COLOR2: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
         let count = self.players.len();
         let scale = 2.0 / (count + 1) as f64;
-        graphics::rectangle(
+        renderer.rectangle(
             COLOR1,
             graphics::rectangle::centered_square(0.0, 0.0, 1.0),
             trans,
-            gl,
         );
         for i in 0..count {
             let transform = trans
                 .trans(scale * (i as f64 + 1.0) - 1.0, 0.0)
                 .rot_rad(self.players[i].rotation);
-            graphics::rectangle(
+            renderer.rectangle(
                 tputil::COLORS[self.players[i].player.color],
                 graphics::rectangle::centered_square(0.0, 0.0, scale / 4.0),
                 transform,
-                gl,
+            );
+            // one eye per difficulty level
+            if let Some(difficulty) = self.players[i].player.cpu {
+                let level = cpu::Difficulty::ALL
+                    .iter()
+                    .position(|&other| other == difficulty)
+                    .unwrap_or(0);
+                for eye in 0..=level {
+                    let x = (eye as f64 - level as f64 / 2.0) * scale / 8.0;
+                    renderer.rectangle(
+                        COLOR1,
+                        graphics::rectangle::centered_square(x, -scale / 16.0, scale / 32.0),
+                        transform,
+                    );
+                }
+            }
+        }
+        if count > 0 {
+            renderer.rectangle(COLOR2, [-1.0, 0.85, 2.0, 0.15], trans);
+            utils.draw_text_align(
+                "Up: add CPU   Down: remove CPU   Start: play",
+                0.06,
+                tputil::Alignment::MIDDLE_CENTER,
+                trans.trans(0.0, 0.925),
+                renderer,
             );
         }
     }
@@ -107,46 +352,339 @@ impl game::State for JoinState {
             if found {
                 continue;
             }
-            let color;
-            let mut colors: Vec<usize> = (0..tputil::COLORS.len()).collect();
-            for player in &self.players {
-                if let Some(pos) = (&colors).iter().position(|&c| c == player.player.color) {
-                    colors.remove(pos);
-                }
-            }
-            color = colors[rand::thread_rng().gen_range(0, colors.len())];
-            self.players.push(JoinStatePlayer::new(p, color));
+            let color = self.free_color(props.rng);
+            self.players.push(JoinStatePlayer::new(p, color, None));
         }
+        let mut add_cpu = false;
+        let mut remove_cpu = false;
         for player in &mut self.players {
             let movement = props.input.get_axis(&player.player.input, tputil::Axis::X);
             player.rotation += f64::from(movement) * props.time * 3.0;
+
+            let movement = props.input.get_axis(&player.player.input, tputil::Axis::Y);
+            if movement.abs() < 0.5 {
+                player.moved = false;
+            } else if !player.moved {
+                player.moved = true;
+                if movement > 0.0 {
+                    add_cpu = true;
+                } else {
+                    remove_cpu = true;
+                }
+            }
+        }
+        if add_cpu && self.players.len() < tputil::COLORS.len() {
+            let color = self.free_color(props.rng);
+            let input = props.input.new_virtual();
+            self.players.push(JoinStatePlayer::new(
+                input,
+                color,
+                Some(props.settings.cpu_difficulty),
+            ));
+        }
+        if remove_cpu {
+            if let Some(index) = self.players.iter().rposition(|p| p.player.cpu.is_some()) {
+                self.players.remove(index);
+            }
         }
 
-        self.players.retain(|p| {
-            !props
+        for player in &mut self.players {
+            if !props
                 .input
-                .is_pressed(&p.player.input, tputil::Button::East)
+                .is_pressed(&player.player.input, tputil::Button::East)
+            {
+                player.can_leave = true;
+            }
+        }
+        self.players.retain(|p| {
+            !p.can_leave
+                || !props
+                    .input
+                    .is_pressed(&p.player.input, tputil::Button::East)
                 || props
                     .input
                     .is_pressed(&p.player.input, tputil::Button::South)
         });
 
-        if !props
-            .input
-            .get_pressed_any(tputil::Button::Start)
-            .is_empty()
+        if !self.players.is_empty()
+            && !props
+                .input
+                .get_pressed_any(tputil::Button::Start)
+                .is_empty()
         {
-            let players: Vec<states::ingame::PlayerInfo> = self
+            let players: Vec<tputil::Player> = self
                 .players
                 .iter()
-                .map(|player| states::ingame::PlayerInfo::from(player.player.clone()))
+                .map(|player| player.player.clone())
                 .collect();
+            if self.free_play {
+                let players: Vec<states::ingame::PlayerInfo> = players
+                    .into_iter()
+                    .map(states::ingame::PlayerInfo::from)
+                    .collect();
+                let game = states::ingame::GameInfo::new(
+                    players,
+                    board::Board::get_default_board(),
+                    1,
+                    props.rng,
+                );
+                return game::UpdateResult::NewState(Box::new(
+                    states::freeplay::FreePlayState::new(game),
+                ));
+            }
+            return game::UpdateResult::NewState(Box::new(BoardSelectState::new(
+                players,
+                props.settings.turns,
+            )));
+        }
+
+        game::UpdateResult::Continue
+    }
+}
+
+struct BoardChoice {
+    name: String,
+    board: Option<board::Board>,
+    errors: Vec<String>,
+}
+
+impl BoardChoice {
+    fn playable(&self) -> bool {
+        self.board.is_some() && self.errors.is_empty()
+    }
+}
+
+pub struct BoardSelectState {
+    players: Vec<tputil::Player>,
+    boards: Vec<BoardChoice>,
+    selected: usize,
+    turns: u16,
+    // one row for the turn count and one for the boards
+    menu: states::menu::Menu,
+}
+
+impl BoardSelectState {
+    const TURNS_ROW: usize = 0;
+    const BOARD_ROW: usize = 1;
+    const ROWS: usize = 2;
+
+    fn new(players: Vec<tputil::Player>, turns: u16) -> Self {
+        let mut menu = states::menu::Menu::new();
+        menu.selected = BoardSelectState::BOARD_ROW;
+        BoardSelectState {
+            players,
+            boards: BoardSelectState::load_boards(),
+            selected: 0,
+            turns,
+            menu,
+        }
+    }
+
+    fn load_boards() -> Vec<BoardChoice> {
+        let mut choices = Vec::new();
+        match board::Board::load_dir(board::BOARDS_DIR) {
+            Ok(boards) => {
+                for (path, result) in boards {
+                    let name = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    choices.push(match result {
+                        Ok(board) => {
+                            let problems = board.validate();
+                            for problem in &problems {
+                                eprintln!("{}: {}", path.display(), problem);
+                            }
+                            BoardChoice {
+                                name: board.name.clone(),
+                                errors: problems
+                                    .iter()
+                                    .filter(|problem| problem.severity() == board::Severity::Error)
+                                    .map(|problem| problem.to_string())
+                                    .collect(),
+                                board: Some(board),
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("{}: {}", path.display(), err);
+                            BoardChoice {
+                                name,
+                                board: None,
+                                errors: vec![err.to_string()],
+                            }
+                        }
+                    });
+                }
+            }
+            Err(err) => eprintln!("Failed to list {}: {}", board::BOARDS_DIR, err),
+        }
+        if !choices.iter().any(BoardChoice::playable) {
             let board = board::Board::get_default_board();
-            let game = states::ingame::GameInfo::new(players, board);
+            choices.insert(
+                0,
+                BoardChoice {
+                    name: board.name.clone(),
+                    board: Some(board),
+                    errors: vec![],
+                },
+            );
+        }
+        choices
+    }
+}
 
-            return game::UpdateResult::NewState(Box::new(states::ingame::DieRollState::new(
-                game, 0,
-            )));
+impl game::State for BoardSelectState {
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        const COLOR1: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+        const COLOR2: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+        const COLOR3: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+        const COLOR4: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+        const CARD_SIZE: f64 = 0.6;
+        const CARD_SPACING: f64 = 0.7;
+
+        utils.draw_text_align(
+            "Choose a board",
+            0.12,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -1.0),
+            renderer,
+        );
+        if self.menu.selected == BoardSelectState::TURNS_ROW {
+            renderer.rectangle(COLOR4, [-0.4, -0.8, 0.8, 0.08], trans);
+        }
+        utils.draw_text_align(
+            &format!("< {} turns >", self.turns),
+            0.06,
+            tputil::Alignment::TOP_CENTER,
+            trans.trans(0.0, -0.8),
+            renderer,
+        );
+
+        for (i, choice) in self.boards.iter().enumerate() {
+            let x = (i as f64 - self.selected as f64) * CARD_SPACING;
+            if x.abs() > 1.0 + CARD_SIZE {
+                continue;
+            }
+            let card = trans.trans(x, -0.2);
+            if i == self.selected {
+                let border = if self.menu.selected == BoardSelectState::BOARD_ROW {
+                    COLOR4
+                } else {
+                    COLOR2
+                };
+                renderer.rectangle(
+                    border,
+                    graphics::rectangle::centered_square(0.0, 0.0, CARD_SIZE / 2.0 + 0.02),
+                    card,
+                );
+            }
+            renderer.rectangle(
+                COLOR1,
+                graphics::rectangle::centered_square(0.0, 0.0, CARD_SIZE / 2.0),
+                card,
+            );
+            match choice.board {
+                Some(ref board) => {
+                    let (min, max) = board.bounds();
+                    let size = (max.x - min.x).max(max.y - min.y) + 2.0;
+                    let scale = CARD_SIZE * 0.9 / size;
+                    let preview = card
+                        .scale(scale, scale)
+                        .trans(-(min.x + max.x) / 2.0, -(min.y + max.y) / 2.0);
+                    states::ingame::render_board(board, None, preview, renderer);
+                }
+                None => {
+                    renderer.line(COLOR3, 0.02, [-0.2, -0.2, 0.2, 0.2], card);
+                    renderer.line(COLOR3, 0.02, [-0.2, 0.2, 0.2, -0.2], card);
+                }
+            }
+            utils.draw_text_align(
+                &choice.name,
+                0.07,
+                tputil::Alignment::TOP_CENTER,
+                card.trans(0.0, CARD_SIZE / 2.0 + 0.03),
+                renderer,
+            );
+            if let Some(ref board) = choice.board {
+                utils.draw_text_align(
+                    &format!("{} spaces", board.spaces.len()),
+                    0.05,
+                    tputil::Alignment::TOP_CENTER,
+                    card.trans(0.0, CARD_SIZE / 2.0 + 0.12),
+                    renderer,
+                );
+            }
+        }
+
+        let choice = &self.boards[self.selected];
+        let text = match choice.errors.first() {
+            Some(error) => format!("This board is broken: {}", error),
+            None => choice
+                .board
+                .as_ref()
+                .map(|board| board.description.clone())
+                .unwrap_or_default(),
+        };
+        if !text.is_empty() {
+            utils.draw_text_align_wrap(
+                &text,
+                0.05,
+                tputil::Alignment::BOTTOM_CENTER,
+                1.8,
+                trans.trans(0.0, 1.0),
+                renderer,
+            );
+        }
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let controllers: Vec<tputil::InputMethod> =
+            self.players.iter().map(|player| player.input).collect();
+        match self
+            .menu
+            .update(props.input, &controllers, BoardSelectState::ROWS)
+        {
+            Some(states::menu::MenuAction::Adjust(row, direction))
+                if row == BoardSelectState::TURNS_ROW =>
+            {
+                self.turns = if direction > 0 {
+                    (self.turns + 1).min(settings::MAX_TURNS)
+                } else {
+                    (self.turns - 1).max(settings::MIN_TURNS)
+                };
+            }
+            Some(states::menu::MenuAction::Adjust(_, direction)) => {
+                let count = self.boards.len();
+                self.selected = if direction > 0 {
+                    (self.selected + 1) % count
+                } else {
+                    (self.selected + count - 1) % count
+                };
+            }
+            Some(states::menu::MenuAction::Select(_)) if self.boards[self.selected].playable() => {
+                let board = self.boards[self.selected].board.clone().unwrap();
+                let players: Vec<states::ingame::PlayerInfo> = self
+                    .players
+                    .iter()
+                    .cloned()
+                    .map(states::ingame::PlayerInfo::from)
+                    .collect();
+                let game = states::ingame::GameInfo::new(players, board, self.turns, props.rng);
+                save::autosave(&game);
+                return game::UpdateResult::NewState(Box::new(states::ingame::DieRollState::new(
+                    game, 0,
+                )));
+            }
+            Some(states::menu::MenuAction::Back) => {
+                return game::UpdateResult::NewState(Box::new(JoinState::rejoin(
+                    self.players.clone(),
+                )));
+            }
+            _ => {}
         }
 
         game::UpdateResult::Continue
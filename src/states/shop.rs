@@ -0,0 +1,132 @@
+use crate::cpu;
+use crate::game;
+use crate::items;
+use crate::render;
+use crate::states;
+use crate::tputil;
+
+use crate::states::ingame::GameInfo;
+
+pub struct ShopState {
+    game: GameInfo,
+    turn: usize,
+    menu: states::menu::Menu,
+    message: Option<&'static str>,
+    thinking: f64,
+}
+
+impl ShopState {
+    pub fn new(game: GameInfo, turn: usize) -> Self {
+        ShopState {
+            game,
+            turn,
+            menu: states::menu::Menu::new(),
+            message: None,
+            thinking: 0.0,
+        }
+    }
+
+    fn leave(&self) -> game::UpdateResult {
+        crate::to_new_state!(|prev: Self| {
+            Box::new(states::ingame::SpaceResultState::new(prev.game, prev.turn))
+        })
+    }
+}
+
+impl game::State for ShopState {
+    fn pausable(&self) -> bool {
+        true
+    }
+    fn render(
+        &self,
+        renderer: &mut dyn render::Renderer,
+        trans: graphics::math::Matrix2d,
+        utils: &mut game::Utils,
+    ) {
+        let transform = self.game.render(
+            renderer,
+            trans,
+            states::ingame::BOARD_CENTER,
+            0.06,
+            utils,
+            &[self.turn],
+        );
+        let player = &self.game.players[self.turn];
+        let space = self.game.map.get_space(player.space).unwrap();
+        renderer.rectangle(
+            tputil::COLORS[player.player.color],
+            graphics::rectangle::centered_square(space.pos.x, space.pos.y, 0.7),
+            transform,
+        );
+
+        let mut choices: Vec<String> = items::Item::ALL
+            .iter()
+            .map(|item| format!("{} ({} coins)", item.name(), item.price()))
+            .collect();
+        choices.push("Leave".to_owned());
+        let description = match self.message {
+            Some(message) => message,
+            None => items::Item::ALL
+                .get(self.menu.selected)
+                .map(|item| item.description())
+                .unwrap_or("Leave without buying anything."),
+        };
+        states::menu::render_choices(
+            "Shop",
+            &choices,
+            self.menu.selected,
+            description,
+            trans,
+            renderer,
+            utils,
+        );
+    }
+    fn update(&mut self, props: game::UpdateProps<'_>) -> game::UpdateResult {
+        let input = self.game.players[self.turn].player.input;
+        let count = items::Item::ALL.len() + 1;
+
+        let previous = self.menu.selected;
+        let action = match self.game.players[self.turn].player.cpu {
+            Some(difficulty) => {
+                self.thinking += props.time;
+                if self.thinking > cpu::THINK_TIME {
+                    self.thinking = 0.0;
+                    let item = cpu::choose_purchase(&self.game, self.turn, difficulty, props.rng);
+                    let selected = item
+                        .and_then(|item| items::Item::ALL.iter().position(|&other| other == item))
+                        .unwrap_or(count - 1);
+                    self.menu.selected = selected;
+                    Some(states::menu::MenuAction::Select(selected))
+                } else {
+                    None
+                }
+            }
+            None => self.menu.update(props.input, &[input], count),
+        };
+        if self.menu.selected != previous {
+            self.message = None;
+        }
+        match action {
+            Some(states::menu::MenuAction::Select(selected)) => {
+                let item = match items::Item::ALL.get(selected) {
+                    Some(&item) => item,
+                    None => return self.leave(),
+                };
+                let player = &mut self.game.players[self.turn];
+                if player.coins < item.price() {
+                    self.message = Some("You don't have enough coins for that.");
+                } else if player.items.len() >= items::MAX_ITEMS {
+                    self.message = Some("You can't carry any more items.");
+                } else {
+                    player.coins -= item.price();
+                    player.items.push(item);
+                    return self.leave();
+                }
+            }
+            Some(states::menu::MenuAction::Back) => return self.leave(),
+            _ => {}
+        }
+
+        game::UpdateResult::Continue
+    }
+}
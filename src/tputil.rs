@@ -1,5 +1,7 @@
 pub use gilrs::Button;
 
+use crate::bindings;
+use crate::cpu;
 use graphics::Transformed;
 
 #[derive(Copy, Clone)]
@@ -16,6 +18,8 @@ pub const COLORS: [[f32; 4]; 5] = [
     [0.0, 1.0, 1.0, 1.0],
 ];
 
+pub const COLOR_NAMES: [&str; 5] = ["Green", "Blue", "Yellow", "Magenta", "Cyan"];
+
 #[derive(Copy, Clone, Debug)]
 pub struct Point2D {
     pub x: f64,
@@ -93,15 +97,26 @@ impl Point2D {
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub enum InputMethod {
     Gamepad(gilrs::GamepadId),
-    Keyboard,
+    // one of the key layouts sharing the keyboard
+    Keyboard(usize),
+    // a gamepad whose state is set by code: bots, replays, tests or remote players
+    Virtual(usize),
 }
 
-const KEYBOARD: InputMethod = InputMethod::Keyboard;
+const KEYBOARD: InputMethod = InputMethod::Keyboard(0);
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Player {
+    // controllers are reassigned when a saved game is resumed
+    #[serde(skip, default = "unassigned_input")]
     pub input: InputMethod,
     pub color: usize,
+    #[serde(default)]
+    pub cpu: Option<cpu::Difficulty>,
+}
+
+fn unassigned_input() -> InputMethod {
+    KEYBOARD
 }
 
 #[derive(Clone, Copy)]
@@ -138,7 +153,6 @@ impl Alignment {
         height: f64,
     ) -> graphics::math::Matrix2d {
         let offset = self.get_text_offset(width, height);
-        println!("the offset is {:?}", offset);
         matrix.trans(offset.x, offset.y)
     }
     pub fn align_x(
@@ -173,76 +187,229 @@ pub enum AlignmentY {
     Bottom,
 }
 
+pub const GAMEPAD_BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+pub const GAMEPAD_AXES: [gilrs::Axis; 8] = [
+    gilrs::Axis::LeftStickX,
+    gilrs::Axis::LeftStickY,
+    gilrs::Axis::LeftZ,
+    gilrs::Axis::RightStickX,
+    gilrs::Axis::RightStickY,
+    gilrs::Axis::RightZ,
+    gilrs::Axis::DPadX,
+    gilrs::Axis::DPadY,
+];
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct GamepadSnapshot {
+    pub id: usize,
+    // bit i is set when GAMEPAD_BUTTONS[i] is pressed
+    pub buttons: u32,
+    pub axes: [f32; 8],
+}
+
+impl GamepadSnapshot {
+    fn neutral(id: usize) -> Self {
+        GamepadSnapshot {
+            id,
+            buttons: 0,
+            axes: [0.0; 8],
+        }
+    }
+
+    fn capture(id: usize, gamepad: &gilrs::Gamepad) -> Self {
+        let mut buttons = 0;
+        for (i, &button) in GAMEPAD_BUTTONS.iter().enumerate() {
+            if gamepad.is_pressed(button) {
+                buttons |= 1 << i;
+            }
+        }
+        let mut axes = [0.0; 8];
+        for (value, &axis) in axes.iter_mut().zip(GAMEPAD_AXES.iter()) {
+            *value = gamepad.value(axis);
+        }
+        GamepadSnapshot { id, buttons, axes }
+    }
+
+    fn is_pressed(&self, button: Button) -> bool {
+        match GAMEPAD_BUTTONS.iter().position(|&b| b == button) {
+            Some(i) => self.buttons & (1 << i) != 0,
+            None => false,
+        }
+    }
+
+    fn value(&self, axis: gilrs::Axis) -> f32 {
+        match GAMEPAD_AXES.iter().position(|&a| a == axis) {
+            Some(i) => self.axes[i],
+            None => 0.0,
+        }
+    }
+
+    fn set_pressed(&mut self, button: Button, pressed: bool) {
+        if let Some(i) = GAMEPAD_BUTTONS.iter().position(|&b| b == button) {
+            if pressed {
+                self.buttons |= 1 << i;
+            } else {
+                self.buttons &= !(1 << i);
+            }
+        }
+    }
+
+    fn set_value(&mut self, axis: gilrs::Axis, value: f32) {
+        if let Some(i) = GAMEPAD_AXES.iter().position(|&a| a == axis) {
+            self.axes[i] = value;
+        }
+    }
+}
+
+struct VirtualPad {
+    state: GamepadSnapshot,
+    // connected pads show up in menus like a plugged in controller
+    connected: bool,
+}
+
+// everything InputState knows during one update
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct InputSnapshot {
+    pub keys: Vec<piston::input::Key>,
+    pub gamepads: Vec<GamepadSnapshot>,
+}
+
 pub struct InputState {
-    backend: gilrs::Gilrs,
+    pub bindings: bindings::Bindings,
+    backend: Option<gilrs::Gilrs>,
     keyboard_state: std::collections::HashMap<piston::input::Key, bool>,
+    virtual_pads: Vec<VirtualPad>,
+    next_virtual: usize,
+    // virtual pad ids of replayed gamepads, by their id at recording time
+    replayed_ids: std::collections::HashMap<usize, usize>,
 }
 
 impl InputState {
     pub fn new() -> Result<Self, gilrs::Error> {
-        Ok(InputState {
-            backend: gilrs::Gilrs::new()?,
+        let mut state = InputState::without_gilrs();
+        state.backend = Some(gilrs::Gilrs::new()?);
+        Ok(state)
+    }
+
+    pub fn without_gilrs() -> Self {
+        InputState {
+            bindings: bindings::Bindings::default(),
+            backend: None,
             keyboard_state: std::collections::HashMap::new(),
-        })
+            virtual_pads: Vec::new(),
+            next_virtual: 0,
+            replayed_ids: std::collections::HashMap::new(),
+        }
+    }
+
+    fn virtual_pad(&self, id: usize) -> Option<&VirtualPad> {
+        self.virtual_pads.iter().find(|pad| pad.state.id == id)
+    }
+
+    fn virtual_pad_mut(&mut self, id: usize) -> &mut VirtualPad {
+        match self.virtual_pads.iter().position(|pad| pad.state.id == id) {
+            Some(index) => &mut self.virtual_pads[index],
+            None => {
+                self.virtual_pads.push(VirtualPad {
+                    state: GamepadSnapshot::neutral(id),
+                    connected: false,
+                });
+                self.virtual_pads.last_mut().unwrap()
+            }
+        }
     }
 
     #[allow(unused_parens)] // https://github.com/rust-lang/rust/issues/71290
     pub fn get_axis(&self, ctl: &InputMethod, axis: Axis) -> f32 {
         match ctl {
-            InputMethod::Gamepad(id) => {
-                if !self.backend.gamepad(*id).is_connected() {
-                    0.0
-                } else {
-                    let raw = &self.backend.gamepad(*id);
-                    match axis {
-                        Axis::X => {
-                            raw.value(gilrs::Axis::LeftStickX) + raw.value(gilrs::Axis::DPadX)
-                        }
-                        Axis::Y => {
-                            raw.value(gilrs::Axis::LeftStickY) + raw.value(gilrs::Axis::DPadY)
-                        }
-                    }
-                    .max(-1.0)
-                    .min(1.0)
-                }
-            }
-            InputMethod::Keyboard => match axis {
-                Axis::X => {
-                    (match self.keyboard_state.get(&piston::input::Key::Left) {
-                        Some(_) => -1.0,
-                        _ => 0.0,
-                    } + match self.keyboard_state.get(&piston::input::Key::Right) {
-                        Some(_) => 1.0,
-                        _ => 0.0,
-                    })
-                }
-                Axis::Y => {
-                    (match self.keyboard_state.get(&piston::input::Key::Up) {
-                        Some(_) => 1.0,
-                        _ => 0.0,
-                    } + match self.keyboard_state.get(&piston::input::Key::Down) {
-                        Some(_) => -1.0,
-                        _ => 0.0,
-                    })
+            InputMethod::Gamepad(id) => match self.backend {
+                Some(ref backend) if backend.gamepad(*id).is_connected() => {
+                    let raw = &backend.gamepad(*id);
+                    self.bindings
+                        .gamepad(raw.name())
+                        .axis(|axis| raw.value(axis), axis)
                 }
+                _ => 0.0,
+            },
+            InputMethod::Virtual(id) => match self.virtual_pad(*id) {
+                // replayed controllers read like the gamepads they were recorded from
+                Some(pad) if pad.connected => self
+                    .bindings
+                    .gamepad
+                    .axis(|axis| pad.state.value(axis), axis),
+                // bots are unmapped, they set exactly the value they want
+                Some(pad) => pad.state.value(match axis {
+                    Axis::X => gilrs::Axis::LeftStickX,
+                    Axis::Y => gilrs::Axis::LeftStickY,
+                }),
+                None => 0.0,
             },
+            InputMethod::Keyboard(slot) => {
+                let layout = match self.bindings.keyboards.get(*slot) {
+                    Some(layout) => layout,
+                    None => return 0.0,
+                };
+                let (negative, positive) = match axis {
+                    Axis::X => (layout.left, layout.right),
+                    Axis::Y => (layout.down, layout.up),
+                };
+                (match self.keyboard_state.get(&negative) {
+                    Some(_) => -1.0,
+                    _ => 0.0,
+                } + match self.keyboard_state.get(&positive) {
+                    Some(_) => 1.0,
+                    _ => 0.0,
+                })
+            }
         }
     }
 
     pub fn is_pressed(&self, ctl: &InputMethod, button: Button) -> bool {
         match ctl {
-            InputMethod::Gamepad(id) => self.backend.gamepad(*id).is_pressed(button),
-            InputMethod::Keyboard => {
-                let key = match button {
-                    Button::South => Some(piston::input::Key::LShift),
-                    Button::Start => Some(piston::input::Key::Return),
-                    _ => None,
-                };
-                match key {
-                    Some(key) => self.keyboard_state.contains_key(&key),
-                    None => false,
+            InputMethod::Gamepad(id) => match self.backend {
+                Some(ref backend) => {
+                    let raw = backend.gamepad(*id);
+                    raw.is_pressed(self.bindings.gamepad(raw.name()).button(button))
                 }
-            }
+                None => false,
+            },
+            InputMethod::Virtual(id) => match self.virtual_pad(*id) {
+                Some(pad) if pad.connected => {
+                    pad.state.is_pressed(self.bindings.gamepad.button(button))
+                }
+                Some(pad) => pad.state.is_pressed(button),
+                None => false,
+            },
+            InputMethod::Keyboard(slot) => match self
+                .bindings
+                .keyboards
+                .get(*slot)
+                .and_then(|layout| layout.key(button))
+            {
+                Some(key) => self.keyboard_state.contains_key(&key),
+                None => false,
+            },
         }
     }
 
@@ -250,26 +417,150 @@ impl InputState {
         self.keyboard_state.contains_key(&key)
     }
 
+    pub fn pressed_keys(&self) -> Vec<piston::input::Key> {
+        self.keyboard_state.keys().cloned().collect()
+    }
+
+    pub fn gamepad_name(&self, id: gilrs::GamepadId) -> Option<String> {
+        self.backend
+            .as_ref()
+            .map(|backend| backend.gamepad(id).name().to_owned())
+    }
+
+    // the physical button held on a gamepad, ignoring bindings
+    pub fn raw_gamepad_button(&self, id: gilrs::GamepadId) -> Option<Button> {
+        let backend = self.backend.as_ref()?;
+        let raw = backend.gamepad(id);
+        GAMEPAD_BUTTONS
+            .iter()
+            .cloned()
+            .find(|&button| raw.is_pressed(button))
+    }
+
+    // a physical axis pushed past halfway on a gamepad, ignoring bindings
+    pub fn raw_gamepad_axis(&self, id: gilrs::GamepadId) -> Option<gilrs::Axis> {
+        let backend = self.backend.as_ref()?;
+        let raw = backend.gamepad(id);
+        GAMEPAD_AXES
+            .iter()
+            .cloned()
+            .find(|&axis| raw.value(axis).abs() > 0.5)
+    }
+
     pub fn get_pressed_any(&self, button: Button) -> Vec<InputMethod> {
+        self.methods()
+            .into_iter()
+            .filter(|method| self.is_pressed(method, button))
+            .collect()
+    }
+
+    // the strongest deflection of any device, for screens no player owns yet
+    pub fn get_axis_any(&self, axis: Axis) -> f32 {
+        self.methods()
+            .iter()
+            .map(|method| self.get_axis(method, axis))
+            .fold(0.0, |best, value| {
+                if value.abs() > best.abs() {
+                    value
+                } else {
+                    best
+                }
+            })
+    }
+
+    fn methods(&self) -> Vec<InputMethod> {
         let mut results = Vec::new();
-        for (id, gamepad) in self.backend.gamepads() {
-            if gamepad.is_pressed(button) {
-                results.push(InputMethod::Gamepad(id))
+        if let Some(ref backend) = self.backend {
+            for (id, _) in backend.gamepads() {
+                results.push(InputMethod::Gamepad(id));
+            }
+        }
+        for pad in &self.virtual_pads {
+            if pad.connected {
+                results.push(InputMethod::Virtual(pad.state.id));
             }
         }
-        if self.is_pressed(&KEYBOARD, button) {
-            results.push(KEYBOARD);
+        for slot in 0..self.bindings.keyboards.len() {
+            results.push(InputMethod::Keyboard(slot));
         }
-        println!("get_pressed_any {}", results.len());
         results
     }
 
     pub fn update(&mut self) {
-        while let Some(event) = self.backend.next_event() {
-            self.backend.update(&event);
+        if let Some(ref mut backend) = self.backend {
+            while let Some(event) = backend.next_event() {
+                backend.update(&event);
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> InputSnapshot {
+        let gamepads = match self.backend {
+            Some(ref backend) => backend
+                .gamepads()
+                .map(|(id, gamepad)| GamepadSnapshot::capture(id.into(), &gamepad))
+                .collect(),
+            None => self
+                .virtual_pads
+                .iter()
+                .filter(|pad| pad.connected)
+                .map(|pad| pad.state.clone())
+                .collect(),
+        };
+        InputSnapshot {
+            keys: self.keyboard_state.keys().cloned().collect(),
+            gamepads,
         }
     }
 
+    pub fn apply_snapshot(&mut self, snapshot: InputSnapshot) {
+        self.keyboard_state = snapshot.keys.into_iter().map(|key| (key, true)).collect();
+        let mut present = Vec::new();
+        for gamepad in snapshot.gamepads {
+            let id = match self.replayed_ids.get(&gamepad.id) {
+                Some(&id) => id,
+                None => {
+                    let id = self.next_virtual;
+                    self.next_virtual += 1;
+                    self.replayed_ids.insert(gamepad.id, id);
+                    id
+                }
+            };
+            let pad = self.virtual_pad_mut(id);
+            pad.state = GamepadSnapshot { id, ..gamepad };
+            pad.connected = true;
+            present.push(id);
+        }
+        // recorded gamepads missing from this frame were unplugged
+        let replayed: Vec<usize> = self.replayed_ids.values().cloned().collect();
+        self.virtual_pads
+            .retain(|pad| !replayed.contains(&pad.state.id) || present.contains(&pad.state.id));
+    }
+
+    // reserves an id for a new virtual pad, which stays neutral until set
+    pub fn new_virtual(&mut self) -> InputMethod {
+        let id = self.next_virtual;
+        self.next_virtual += 1;
+        InputMethod::Virtual(id)
+    }
+
+    pub fn remove_virtual(&mut self, id: usize) {
+        self.virtual_pads.retain(|pad| pad.state.id != id);
+    }
+
+    pub fn set_virtual_button(&mut self, id: usize, button: Button, pressed: bool) {
+        self.virtual_pad_mut(id).state.set_pressed(button, pressed);
+    }
+
+    // drives the left stick, which get_axis reads back unchanged for bots
+    pub fn set_virtual_axis(&mut self, id: usize, axis: Axis, value: f32) {
+        let axis = match axis {
+            Axis::X => gilrs::Axis::LeftStickX,
+            Axis::Y => gilrs::Axis::LeftStickY,
+        };
+        self.virtual_pad_mut(id).state.set_value(axis, value);
+    }
+
     pub fn on_key_press(&mut self, key: piston::input::Key) {
         self.keyboard_state.insert(key, true);
     }
@@ -341,3 +632,88 @@ pub fn str_split_ranges(
         finished: false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_pads_read_back() {
+        let mut input = InputState::without_gilrs();
+        let pad = input.new_virtual();
+        let id = match pad {
+            InputMethod::Virtual(id) => id,
+            _ => unreachable!(),
+        };
+        assert!(!input.is_pressed(&pad, Button::South));
+        assert_eq!(input.get_axis(&pad, Axis::X), 0.0);
+
+        input.set_virtual_button(id, Button::South, true);
+        input.set_virtual_axis(id, Axis::X, 0.75);
+        input.set_virtual_axis(id, Axis::Y, -1.0);
+        assert!(input.is_pressed(&pad, Button::South));
+        assert!(!input.is_pressed(&pad, Button::East));
+        assert!((input.get_axis(&pad, Axis::X) - 0.75).abs() < 1e-6);
+        assert!((input.get_axis(&pad, Axis::Y) + 1.0).abs() < 1e-6);
+
+        // bots have no deadzone
+        input.set_virtual_axis(id, Axis::X, 0.05);
+        assert!((input.get_axis(&pad, Axis::X) - 0.05).abs() < 1e-6);
+
+        input.set_virtual_button(id, Button::South, false);
+        assert!(!input.is_pressed(&pad, Button::South));
+
+        input.remove_virtual(id);
+        assert!(!input.is_pressed(&pad, Button::South));
+        assert_eq!(input.get_axis(&pad, Axis::Y), 0.0);
+    }
+
+    #[test]
+    fn virtual_pads_are_separate() {
+        let mut input = InputState::without_gilrs();
+        let first = input.new_virtual();
+        let second = input.new_virtual();
+        if let InputMethod::Virtual(id) = first {
+            input.set_virtual_button(id, Button::South, true);
+        }
+        assert!(input.is_pressed(&first, Button::South));
+        assert!(!input.is_pressed(&second, Button::South));
+    }
+
+    #[test]
+    fn replayed_pads_use_the_gamepad_bindings() {
+        let mut input = InputState::without_gilrs();
+        input.bindings.gamepad.deadzone = 0.3;
+        input.bindings.gamepad.invert_y = true;
+        input
+            .bindings
+            .gamepad
+            .set_button(Button::South, Button::East);
+        let replay = |x: f32, y: f32, east: bool| {
+            let mut axes = [0.0; 8];
+            axes[0] = x;
+            axes[1] = y;
+            let mut state = GamepadSnapshot {
+                id: 3,
+                buttons: 0,
+                axes,
+            };
+            state.set_pressed(Button::East, east);
+            InputSnapshot {
+                keys: Vec::new(),
+                gamepads: vec![state],
+            }
+        };
+        let pad = InputMethod::Virtual(0);
+
+        input.apply_snapshot(replay(0.2, 0.5, true));
+        assert_eq!(input.get_axis(&pad, Axis::X), 0.0);
+        assert!((input.get_axis(&pad, Axis::Y) + 0.5).abs() < 1e-6);
+        assert!(input.is_pressed(&pad, Button::South));
+
+        input.apply_snapshot(replay(0.4, -0.2, false));
+        assert!((input.get_axis(&pad, Axis::X) - 0.4).abs() < 1e-6);
+        assert_eq!(input.get_axis(&pad, Axis::Y), 0.0);
+        assert!(!input.is_pressed(&pad, Button::South));
+    }
+}
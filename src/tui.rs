@@ -0,0 +1,119 @@
+use crate::bindings;
+use crate::game;
+use crate::render;
+use crate::tputil;
+
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+use piston::input::Key;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const FRAME_TIME: Duration = Duration::from_millis(33);
+// terminals only report presses, and key repeat starts after about half a second
+const HOLD_TIME: f64 = 0.55;
+
+// stands in for the first keyboard layout, whatever keys it was given
+fn map_key(code: KeyCode, layout: &bindings::KeyLayout) -> Option<Key> {
+    match code {
+        KeyCode::Left => Some(layout.left),
+        KeyCode::Right => Some(layout.right),
+        KeyCode::Up => Some(layout.up),
+        KeyCode::Down => Some(layout.down),
+        KeyCode::Char(' ') | KeyCode::Char('z') => layout.key(tputil::Button::South),
+        KeyCode::Enter => layout.key(tputil::Button::Start),
+        KeyCode::Esc => Some(Key::Escape),
+        _ => None,
+    }
+}
+
+// puts the terminal back however run returns, including by panicking
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn restore() {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        TerminalGuard::restore();
+    }
+}
+
+pub fn run(mut app: game::App) -> crossterm::Result<()> {
+    let mut stdout = std::io::stdout();
+
+    terminal::enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    // otherwise the panic message is printed to the alternate screen and lost
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        TerminalGuard::restore();
+        default_hook(info);
+    }));
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    run_loop(&mut app, &mut stdout)
+}
+
+fn run_loop(app: &mut game::App, stdout: &mut std::io::Stdout) -> crossterm::Result<()> {
+    let mut held: Vec<(Key, f64)> = Vec::new();
+    let mut last = Instant::now();
+    loop {
+        while event::poll(Duration::from_secs(0))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(())
+                    }
+                    code => {
+                        let key = app
+                            .input
+                            .bindings
+                            .keyboards
+                            .first()
+                            .and_then(|layout| map_key(code, layout));
+                        if let Some(key) = key {
+                            app.input.on_key_press(key);
+                            held.retain(|&(other, _)| other != key);
+                            held.push((key, HOLD_TIME));
+                        }
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let dt = (now - last).as_secs_f64();
+        last = now;
+        app.advance(dt);
+        if app.quit {
+            return Ok(());
+        }
+
+        for held_key in &mut held {
+            held_key.1 -= dt;
+            if held_key.1 <= 0.0 {
+                app.input.on_key_release(held_key.0);
+            }
+        }
+        held.retain(|&(_, remaining)| remaining > 0.0);
+
+        let (columns, rows) = terminal::size()?;
+        let mut renderer = render::terminal::TerminalRenderer::new(columns, rows);
+        app.render(graphics::math::identity(), renderer.area(), &mut renderer);
+        renderer.present(stdout)?;
+
+        let elapsed = now.elapsed();
+        if elapsed < FRAME_TIME {
+            std::thread::sleep(FRAME_TIME - elapsed);
+        }
+    }
+}